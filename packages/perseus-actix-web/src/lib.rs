@@ -12,15 +12,18 @@ documentation, and this should mostly be used as a secondary reference source. Y
 
 use actix_files::{Files, NamedFile};
 use actix_web::CustomizeResponder;
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
-use perseus::turbine::ApiResponse as PerseusApiResponse;
+use actix_web::{web, Either, HttpRequest, HttpResponse, Responder};
+use futures::StreamExt;
+use perseus::turbine::{
+    ApiResponse as PerseusApiResponse, StreamingApiResponse as PerseusStreamingApiResponse,
+};
 use perseus::{
     http::StatusCode,
     i18n::TranslationsManager,
     path::*,
     server::ServerOptions,
     stores::MutableStore,
-    turbine::{SubsequentLoadQueryParams, Turbine},
+    turbine::{RevalidationQueryParams, SubsequentLoadQueryParams, Turbine},
     Request,
 };
 
@@ -68,6 +71,24 @@ impl Responder for ApiResponse {
     }
 }
 
+#[derive(Debug)]
+struct StreamingApiResponse(PerseusStreamingApiResponse<'static>);
+impl Responder for StreamingApiResponse {
+    type Body = actix_web::body::BoxBody;
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let mut res = HttpResponse::build(self.0.status);
+        for header in self.0.headers {
+            res.insert_header((header.0.unwrap(), header.1));
+        }
+        // Each chunk will be sent as soon as it's ready
+        res.streaming(
+            self.0
+                .body
+                .map(|chunk| Ok::<_, std::convert::Infallible>(web::Bytes::from(chunk))),
+        )
+    }
+}
+
 // ----- Integration code -----
 
 /// Configures an existing Actix Web app for Perseus. This returns a function
@@ -80,6 +101,9 @@ pub async fn configurer<M: MutableStore + 'static, T: TranslationsManager + 'sta
 ) -> impl FnOnce(&mut actix_web::web::ServiceConfig) {
     move |cfg: &mut web::ServiceConfig| {
         let snippets_dir = opts.snippets.clone();
+        let revalidation_secret = opts.revalidation_secret.clone();
+        let health_path = opts.health_path.clone();
+        let stream_initial_loads = opts.stream_initial_loads;
         cfg
             .app_data(web::Data::new(opts))
             // --- File handlers ---
@@ -129,6 +153,35 @@ pub async fn configurer<M: MutableStore + 'static, T: TranslationsManager + 'sta
                     ).await)
                 }),
             );
+        // --- On-demand revalidation handler (only if a secret has been set) ---
+        if let Some(secret) = revalidation_secret {
+            cfg.route(
+                "/.perseus/revalidate",
+                web::post().to(move |http_req: HttpRequest, web::Query(query_params): web::Query<RevalidationQueryParams>| {
+                    let secret = secret.clone();
+                    async move {
+                        let http_req = match convert_req(&http_req) {
+                            Ok(req) => req,
+                            Err(err) => return ApiResponse(PerseusApiResponse::err(StatusCode::BAD_REQUEST, &err))
+                        };
+
+                        ApiResponse(turbine.post_revalidate(query_params, &secret, http_req).await)
+                    }
+                }),
+            );
+        }
+        // --- Health check handlers (only if a path has been set) ---
+        if let Some(health_path) = health_path {
+            let health_path = health_path.trim_end_matches('/');
+            cfg.route(
+                &format!("{}/live", health_path),
+                web::get().to(move || async move { ApiResponse(turbine.get_liveness()) }),
+            )
+            .route(
+                &format!("{}/ready", health_path),
+                web::get().to(move || async move { ApiResponse(turbine.get_readiness().await) }),
+            );
+        }
         // --- Static directory and alias handlers
         if turbine.static_dir.exists() {
             cfg.service(Files::new("/.perseus/static", &turbine.static_dir));
@@ -147,14 +200,25 @@ pub async fn configurer<M: MutableStore + 'static, T: TranslationsManager + 'sta
                 let http_req = match convert_req(&http_req) {
                     Ok(req) => req,
                     Err(err) => {
-                        return ApiResponse(PerseusApiResponse::err(StatusCode::BAD_REQUEST, &err))
+                        return Either::Left(ApiResponse(PerseusApiResponse::err(
+                            StatusCode::BAD_REQUEST,
+                            &err,
+                        )))
                     }
                 };
-                ApiResponse(
-                    turbine
-                        .get_initial_load(PathMaybeWithLocale(raw_path), http_req)
-                        .await,
-                )
+                if stream_initial_loads {
+                    Either::Right(StreamingApiResponse(
+                        turbine
+                            .get_initial_load_stream(PathMaybeWithLocale(raw_path), http_req)
+                            .await,
+                    ))
+                } else {
+                    Either::Left(ApiResponse(
+                        turbine
+                            .get_initial_load(PathMaybeWithLocale(raw_path), http_req)
+                            .await,
+                    ))
+                }
             }),
         );
     }
@@ -229,6 +293,10 @@ pub async fn dflt_server<M: MutableStore + 'static, T: TranslationsManager + 'st
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     use actix_web::{App, HttpServer};
     use futures::executor::block_on;
     // TODO Fix issues here
@@ -263,6 +331,10 @@ pub async fn dflt_server_with_compression<
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     use actix_web::{App, HttpServer};
     use futures::executor::block_on;
     // TODO Fix issues here
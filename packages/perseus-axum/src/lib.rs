@@ -11,20 +11,23 @@ documentation, and this should mostly be used as a secondary reference source. Y
 #![deny(missing_debug_implementations)]
 
 use axum::{
-    body::Body,
+    body::{Body, StreamBody},
     extract::{Path, Query},
     http::{Request, StatusCode},
     response::{IntoResponse, Response},
-    routing::{get, get_service},
+    routing::{get, get_service, post},
     Router,
 };
-use perseus::turbine::ApiResponse as PerseusApiResponse;
+use futures::StreamExt;
+use perseus::turbine::{
+    ApiResponse as PerseusApiResponse, StreamingApiResponse as PerseusStreamingApiResponse,
+};
 use perseus::{
     i18n::TranslationsManager,
     path::*,
     server::ServerOptions,
     stores::MutableStore,
-    turbine::{SubsequentLoadQueryParams, Turbine},
+    turbine::{RevalidationQueryParams, SubsequentLoadQueryParams, Turbine},
 };
 use tower_http::services::{ServeDir, ServeFile};
 
@@ -51,6 +54,17 @@ impl IntoResponse for ApiResponse {
     }
 }
 
+#[derive(Debug)]
+struct StreamingApiResponse(PerseusStreamingApiResponse<'static>);
+
+impl IntoResponse for StreamingApiResponse {
+    fn into_response(self) -> Response {
+        // Each chunk will be sent as soon as it's ready
+        let body = StreamBody::new(self.0.body.map(Ok::<_, std::convert::Infallible>));
+        (self.0.status, self.0.headers, body).into_response()
+    }
+}
+
 // ----- Integration code -----
 
 /// Gets the `Router` needed to configure an existing Axum app for Perseus, and
@@ -59,6 +73,7 @@ pub async fn get_router<M: MutableStore + 'static, T: TranslationsManager + 'sta
     turbine: &'static Turbine<M, T>,
     opts: ServerOptions,
 ) -> Router {
+    let stream_initial_loads = opts.stream_initial_loads;
     let router = Router::new()
         // --- File handlers ---
         .route(
@@ -143,6 +158,35 @@ pub async fn get_router<M: MutableStore + 'static, T: TranslationsManager + 'sta
             ),
         );
 
+    // --- On-demand revalidation handler (only if a secret has been set) ---
+    if let Some(secret) = opts.revalidation_secret {
+        router = router.route(
+            "/.perseus/revalidate",
+            post(
+                move |Query(query_params): Query<RevalidationQueryParams>,
+                      http_req: Request<Body>| async move {
+                    let req = Request::from_parts(http_req.into_parts().0, ());
+
+                    ApiResponse(turbine.post_revalidate(query_params, &secret, req).await)
+                },
+            ),
+        );
+    }
+
+    // --- Health check handlers (only if a path has been set) ---
+    if let Some(health_path) = opts.health_path {
+        let health_path = health_path.trim_end_matches('/');
+        router = router
+            .route(
+                &format!("{}/live", health_path),
+                get(move || async move { ApiResponse(turbine.get_liveness()) }),
+            )
+            .route(
+                &format!("{}/ready", health_path),
+                get(move || async move { ApiResponse(turbine.get_readiness().await) }),
+            );
+    }
+
     // --- Static directory and alias handlers ---
     if turbine.static_dir.exists() {
         router = router.nest_service(
@@ -164,11 +208,21 @@ pub async fn get_router<M: MutableStore + 'static, T: TranslationsManager + 'sta
         let path = http_req.uri().path().to_string();
         let http_req = Request::from_parts(http_req.into_parts().0, ());
 
-        ApiResponse(
-            turbine
-                .get_initial_load(PathMaybeWithLocale(path), http_req)
-                .await,
-        )
+        if stream_initial_loads {
+            StreamingApiResponse(
+                turbine
+                    .get_initial_load_stream(PathMaybeWithLocale(path), http_req)
+                    .await,
+            )
+            .into_response()
+        } else {
+            ApiResponse(
+                turbine
+                    .get_initial_load(PathMaybeWithLocale(path), http_req)
+                    .await,
+            )
+            .into_response()
+        }
     }))
 }
 
@@ -189,6 +243,10 @@ pub async fn dflt_server<M: MutableStore + 'static, T: TranslationsManager + 'st
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     use std::net::SocketAddr;
 
     let addr: SocketAddr = format!("{}:{}", host, port)
@@ -216,6 +274,10 @@ pub async fn dflt_server_with_compression<
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     use std::net::SocketAddr;
 
     let addr: SocketAddr = format!("{}:{}", host, port)
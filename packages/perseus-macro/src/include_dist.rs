@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::{LitStr, Result};
+
+/// The arguments to `include_dist!()`, which is just an optional path to the
+/// `dist/` directory.
+pub struct IncludeDistArgs {
+    /// The path to the `dist/` directory, relative to the root of the crate.
+    pub path: Option<LitStr>,
+}
+impl Parse for IncludeDistArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.is_empty() {
+            Ok(Self { path: None })
+        } else {
+            Ok(Self {
+                path: Some(input.parse()?),
+            })
+        }
+    }
+}
+
+pub fn include_dist_impl(args: IncludeDistArgs) -> TokenStream {
+    let dist_path = args
+        .path
+        .as_ref()
+        .map(|path| path.value())
+        .unwrap_or_else(|| "dist".to_string());
+    let manifest_dir = match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(dir) => dir,
+        Err(_) => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "couldn't find the root of the crate (is this being compiled with cargo?)",
+            )
+            .to_compile_error()
+        }
+    };
+    let root = PathBuf::from(manifest_dir).join(dist_path);
+
+    // We only need the artifacts the server reads from the immutable store
+    let mut files = Vec::new();
+    let render_cfg = root.join("render_conf.json");
+    if render_cfg.is_file() {
+        files.push(("render_conf.json".to_string(), render_cfg));
+    }
+    if let Err(err) = collect_files(&root, &root.join("static"), &mut files) {
+        return syn::Error::new(
+            args.path
+                .map(|path| path.span())
+                .unwrap_or_else(proc_macro2::Span::call_site),
+            format!("couldn't read build artifacts: {}", err),
+        )
+        .to_compile_error();
+    }
+
+    let entries = files.iter().map(|(name, path)| {
+        let path = path.to_string_lossy();
+        quote! { (#name, include_bytes!(#path) as &'static [u8]) }
+    });
+
+    // If the app hasn't been built yet, this will just embed nothing, and everything
+    // will be read from the filesystem (which is what happens in the build process)
+    quote! {
+        {
+            #[cfg(engine)]
+            let files: &'static [(&'static str, &'static [u8])] = &[ #(#entries),* ];
+            // We definitely don't want to put all this into the Wasm bundle!
+            #[cfg(not(engine))]
+            let files: &'static [(&'static str, &'static [u8])] = &[];
+            ::perseus::stores::EmbeddedImmutableStore::new(files)
+        }
+    }
+}
+
+/// Recursively collects all the files in the given directory, naming them
+/// relative to the given root (with forward slashes, as the stores expect).
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, PathBuf)>,
+) -> std::io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            let name = rel
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((name, path));
+        }
+    }
+
+    Ok(())
+}
@@ -13,6 +13,7 @@ documentation, and this should mostly be used as a secondary reference source. Y
 
 mod auto_scope;
 mod entrypoint;
+mod include_dist;
 mod rx_state;
 mod test;
 
@@ -165,6 +166,26 @@ pub fn engine_main(_args: TokenStream, input: TokenStream) -> TokenStream {
     entrypoint::engine_main_impl(parsed).into()
 }
 
+/// Embeds the build artifacts of your app (`render_conf.json` and everything in
+/// `static/`) from the `dist/` directory into the engine binary, producing an
+/// `EmbeddedImmutableStore` that can be provided to
+/// `PerseusApp::immutable_store()`. This allows deploying your app as a single
+/// binary, without needing to ship the `dist/` directory alongside it.
+///
+/// You can optionally provide the path to the `dist/` directory relative to
+/// the root of your crate, if you've changed it (e.g.
+/// `include_dist!("custom_dist")`).
+///
+/// Note that this embeds whatever artifacts exist at compile-time, so you'll
+/// need to build your app before compiling the binary you deploy. Nothing is
+/// embedded into the Wasm bundle.
+#[proc_macro]
+pub fn include_dist(input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(input as include_dist::IncludeDistArgs);
+
+    include_dist::include_dist_impl(args).into()
+}
+
 /// Processes the given `struct` to create a reactive version by wrapping each
 /// field in a `Signal`. This will generate a new `struct` with the given name
 /// and implement a `.make_rx()` method on the original that allows turning an
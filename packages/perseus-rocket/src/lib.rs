@@ -16,13 +16,17 @@ use perseus::{
     path::PathMaybeWithLocale,
     server::ServerOptions,
     stores::MutableStore,
-    turbine::{ApiResponse as PerseusApiResponse, Turbine},
+    turbine::{
+        ApiResponse as PerseusApiResponse, RevalidationQueryParams,
+        StreamingApiResponse as PerseusStreamingApiResponse, Turbine,
+    },
 };
 use rocket::{
     fs::{FileServer, NamedFile},
+    futures::StreamExt,
     get,
     http::{Method, Status},
-    response::Responder,
+    response::{stream::ReaderStream, Responder},
     route::{Handler, Outcome},
     routes,
     tokio::fs::File,
@@ -60,6 +64,29 @@ impl<'r> Responder<'r, 'static> for ApiResponse {
     }
 }
 
+#[derive(Debug)]
+struct StreamingApiResponse(PerseusStreamingApiResponse<'static>);
+impl<'r> Responder<'r, 'static> for StreamingApiResponse {
+    fn respond_to(self, _request: &'r rocket::Request<'_>) -> rocket::response::Result<'static> {
+        let mut resp_build = Response::build();
+        resp_build
+            .status(rocket::http::Status {
+                code: self.0.status.into(),
+            })
+            // Each chunk will be sent as soon as it's ready
+            .streamed_body(ReaderStream::from(self.0.body.map(Cursor::new)));
+
+        for h in self.0.headers.iter() {
+            // As above, headers with non-visible ascii characters are chopped off
+            if let Ok(value) = h.1.to_str() {
+                resp_build.raw_header(h.0.to_string(), value.to_string());
+            }
+        }
+
+        resp_build.ok()
+    }
+}
+
 // ----- Simple routes -----
 
 #[get("/bundle.js")]
@@ -130,7 +157,8 @@ where
 
 async fn perseus_initial_load_handler<'r, M, T>(
     req: &'r Request<'_>,
-    turbine: &Turbine<M, T>,
+    turbine: &'static Turbine<M, T>,
+    stream: bool,
 ) -> Outcome<'r>
 where
     M: MutableStore + 'static,
@@ -147,6 +175,14 @@ where
     }
 
     match http_req.body(()) {
+        Ok(r) if stream => Outcome::from(
+            req,
+            StreamingApiResponse(
+                turbine
+                    .get_initial_load_stream(PathMaybeWithLocale(path), r)
+                    .await,
+            ),
+        ),
         Ok(r) => Outcome::from(
             req,
             ApiResponse(turbine.get_initial_load(PathMaybeWithLocale(path), r).await),
@@ -204,6 +240,43 @@ where
     }
 }
 
+async fn perseus_revalidate_handler<'r, M, T>(
+    req: &'r Request<'_>,
+    turbine: &Turbine<M, T>,
+    secret: &str,
+) -> Outcome<'r>
+where
+    M: MutableStore + 'static,
+    T: TranslationsManager + 'static,
+{
+    let path = match req.query_value::<&str>("path").and_then(|res| res.ok()) {
+        Some(path) => path.to_string(),
+        None => return Outcome::Error(Status::BadRequest),
+    };
+    let locale = req
+        .query_value::<&str>("locale")
+        .and_then(|res| res.ok())
+        .map(|locale| locale.to_string());
+
+    let mut http_req = rocket::http::hyper::Request::builder();
+    http_req = http_req.method("POST");
+    for h in req.headers().iter() {
+        http_req = http_req.header(h.name.to_string(), h.value.to_string());
+    }
+
+    match http_req.body(()) {
+        Ok(r) => Outcome::from(
+            req,
+            ApiResponse(
+                turbine
+                    .post_revalidate(RevalidationQueryParams { path, locale }, secret, r)
+                    .await,
+            ),
+        ),
+        _ => Outcome::Error(Status::BadRequest),
+    }
+}
+
 // ----- Rocket handler trait implementation -----
 
 #[derive(Clone)]
@@ -212,8 +285,11 @@ enum PerseusRouteKind<'a> {
     LocalizedInitialConsts,
     UnlocalizedInitialConsts,
     StaticAlias(&'a String),
-    IntialLoadHandler,
+    IntialLoadHandler { stream: bool },
     SubsequentLoadHandler,
+    Revalidate(String),
+    Liveness,
+    Readiness,
 }
 
 #[derive(Clone)]
@@ -244,12 +320,21 @@ where
             PerseusRouteKind::StaticAlias(static_alias) => {
                 perseus_static_alias(req, static_alias).await
             }
-            PerseusRouteKind::IntialLoadHandler => {
-                perseus_initial_load_handler(req, self.turbine).await
+            PerseusRouteKind::IntialLoadHandler { stream } => {
+                perseus_initial_load_handler(req, self.turbine, stream).await
             }
             PerseusRouteKind::SubsequentLoadHandler => {
                 perseus_subsequent_load_handler(req, self.turbine).await
             }
+            PerseusRouteKind::Revalidate(ref secret) => {
+                perseus_revalidate_handler(req, self.turbine, secret).await
+            }
+            PerseusRouteKind::Liveness => {
+                Outcome::from(req, ApiResponse(self.turbine.get_liveness()))
+            }
+            PerseusRouteKind::Readiness => {
+                Outcome::from(req, ApiResponse(self.turbine.get_readiness().await))
+            }
         }
     }
 }
@@ -308,7 +393,9 @@ where
         "/<path..>",
         RocketHandlerWithTurbine {
             turbine,
-            perseus_route: PerseusRouteKind::IntialLoadHandler,
+            perseus_route: PerseusRouteKind::IntialLoadHandler {
+                stream: opts.stream_initial_loads,
+            },
         },
     );
 
@@ -330,11 +417,50 @@ where
         get_localized_initial_consts,
     ]);
 
+    // On-demand revalidation is only exposed if a secret has been set
+    if let Some(secret) = opts.revalidation_secret.clone() {
+        perseus_routes.push(Route::new(
+            Method::Post,
+            "/revalidate",
+            RocketHandlerWithTurbine {
+                turbine,
+                perseus_route: PerseusRouteKind::Revalidate(secret),
+            },
+        ));
+    }
+
     let mut app = rocket::build()
         .manage(opts.clone())
         .mount("/.perseus/", perseus_routes)
         .mount("/", vec![get_initial_load_handler]);
 
+    // Health checks are only exposed if a path has been set
+    if let Some(health_path) = &opts.health_path {
+        let health_routes = vec![
+            Route::new(
+                Method::Get,
+                "/live",
+                RocketHandlerWithTurbine {
+                    turbine,
+                    perseus_route: PerseusRouteKind::Liveness,
+                },
+            ),
+            Route::new(
+                Method::Get,
+                "/ready",
+                RocketHandlerWithTurbine {
+                    turbine,
+                    perseus_route: PerseusRouteKind::Readiness,
+                },
+            ),
+        ];
+        let health_path = match health_path.trim_end_matches('/') {
+            "" => "/",
+            health_path => health_path,
+        };
+        app = app.mount(health_path, health_routes);
+    }
+
     if Path::new(&opts.snippets).exists() {
         app = app.mount("/.perseus/snippets", FileServer::from(opts.snippets))
     }
@@ -373,6 +499,10 @@ pub async fn dflt_server<M: MutableStore + 'static, T: TranslationsManager + 'st
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        rocket::tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     let addr = host.parse().expect("Invalid address provided to bind to.");
 
     let mut app = perseus_base_app(turbine, opts).await;
@@ -403,6 +533,10 @@ pub async fn dflt_server_with_compression<
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        rocket::tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     let addr = host.parse().expect("Invalid address provided to bind to.");
 
     let mut app = perseus_base_app(turbine, opts).await;
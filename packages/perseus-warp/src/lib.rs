@@ -14,14 +14,17 @@ documentation, and this should mostly be used as a secondary reference source. Y
 mod static_content;
 use crate::static_content::{serve_file, static_aliases_filter};
 
+use futures::StreamExt;
 use perseus::http;
-use perseus::turbine::ApiResponse as PerseusApiResponse;
+use perseus::turbine::{
+    ApiResponse as PerseusApiResponse, StreamingApiResponse as PerseusStreamingApiResponse,
+};
 use perseus::{
     i18n::TranslationsManager,
     path::*,
     server::ServerOptions,
     stores::MutableStore,
-    turbine::{SubsequentLoadQueryParams, Turbine},
+    turbine::{RevalidationQueryParams, SubsequentLoadQueryParams, Turbine},
     Request,
 };
 use std::{path::PathBuf, sync::Arc};
@@ -80,6 +83,20 @@ impl Reply for ApiResponse {
     }
 }
 
+#[derive(Debug)]
+struct StreamingApiResponse(PerseusStreamingApiResponse<'static>);
+impl Reply for StreamingApiResponse {
+    fn into_response(self) -> Response {
+        // Each chunk will be sent as soon as it's ready
+        let body =
+            warp::hyper::Body::wrap_stream(self.0.body.map(Ok::<_, std::convert::Infallible>));
+        let mut response = Response::new(body);
+        *response.status_mut() = self.0.status;
+        *response.headers_mut() = self.0.headers;
+        response
+    }
+}
+
 // ----- Integration code -----
 
 /// The routes for Perseus. These will configure an existing Warp instance to
@@ -89,6 +106,7 @@ pub async fn perseus_routes<M: MutableStore + 'static, T: TranslationsManager +
     turbine: &'static Turbine<M, T>,
     opts: ServerOptions,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let stream_initial_loads = opts.stream_initial_loads;
     // --- File handlers ---
     let js_bundle = warp::path!(".perseus" / "bundle.js")
         .and(warp::path::end())
@@ -173,6 +191,50 @@ pub async fn perseus_routes<M: MutableStore + 'static, T: TranslationsManager +
             },
         );
 
+    // --- On-demand revalidation handler (only if a secret has been set) ---
+    let revalidation_secret = Arc::new(opts.revalidation_secret);
+    let revalidate = warp::post()
+        .and(warp::path!(".perseus" / "revalidate"))
+        .and(warp::query::<RevalidationQueryParams>())
+        .and(get_http_req())
+        .and_then(
+            move |query_params: RevalidationQueryParams, http_req: Request| {
+                let revalidation_secret = revalidation_secret.clone();
+                async move {
+                    match revalidation_secret.as_ref() {
+                        Some(secret) => Ok(ApiResponse(
+                            turbine
+                                .post_revalidate(query_params, secret, http_req)
+                                .await,
+                        )),
+                        None => Err(warp::reject::not_found()),
+                    }
+                }
+            },
+        );
+
+    // --- Health check handlers (only if a path has been set) ---
+    // The path isn't known at compile-time, so we have to match it manually
+    let health_path = Arc::new(
+        opts.health_path
+            .map(|path| path.trim_end_matches('/').to_string()),
+    );
+    let health = warp::get()
+        .and(warp::path::full())
+        .and_then(move |path: FullPath| {
+            let health_path = health_path.clone();
+            async move {
+                match health_path
+                    .as_deref()
+                    .and_then(|health_path| path.as_str().strip_prefix(health_path))
+                {
+                    Some("/live") => Ok(ApiResponse(turbine.get_liveness())),
+                    Some("/ready") => Ok(ApiResponse(turbine.get_readiness().await)),
+                    _ => Err(warp::reject::not_found()),
+                }
+            }
+        });
+
     // --- Static directory and alias handlers ---
     let static_dir_path = Arc::new(turbine.static_dir.clone());
     let static_dir_path_filter = warp::any().map(move || static_dir_path.clone());
@@ -197,11 +259,13 @@ pub async fn perseus_routes<M: MutableStore + 'static, T: TranslationsManager +
         .and(warp::path::full())
         .and(get_http_req())
         .then(move |path: FullPath, http_req: Request| async move {
-            ApiResponse(
-                turbine
-                    .get_initial_load(PathMaybeWithLocale(path.as_str().to_string()), http_req)
-                    .await,
-            )
+            let path = PathMaybeWithLocale(path.as_str().to_string());
+            if stream_initial_loads {
+                StreamingApiResponse(turbine.get_initial_load_stream(path, http_req).await)
+                    .into_response()
+            } else {
+                ApiResponse(turbine.get_initial_load(path, http_req).await).into_response()
+            }
         });
 
     // Now put all those routes together in the final thing (the user will add this
@@ -219,6 +283,8 @@ pub async fn perseus_routes<M: MutableStore + 'static, T: TranslationsManager +
         .or(localized_initial_consts)
         .or(unlocalized_initial_consts)
         .or(page_data)
+        .or(revalidate)
+        .or(health)
         .or(initial_loads)
 }
 
@@ -233,6 +299,10 @@ pub async fn dflt_server<M: MutableStore + 'static, T: TranslationsManager + 'st
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     use std::net::SocketAddr;
 
     let addr: SocketAddr = format!("{}:{}", host, port)
@@ -255,6 +325,10 @@ pub async fn dflt_server_with_compression<
     opts: ServerOptions,
     (host, port): (String, u16),
 ) {
+    // Start the revalidation scheduler alongside the server if it's been enabled
+    if let Some(scheduler_opts) = opts.revalidation_scheduler.clone() {
+        tokio::spawn(turbine.run_revalidation_scheduler(scheduler_opts));
+    }
     use std::net::SocketAddr;
 
     let addr: SocketAddr = format!("{}:{}", host, port)
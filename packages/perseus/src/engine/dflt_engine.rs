@@ -167,8 +167,12 @@ where
             // unused memory, so this shouldn't be a problem.
             let turbine_static = Box::leak(Box::new(turbine));
 
-            // We have access to default server options when `dflt-engine` is enabled
-            serve_fn(turbine_static, ServerOptions::default(), addr).await;
+            // We have access to default server options when `dflt-engine` is enabled, and
+            // we run any background tasks alongside the server (stopping them when it stops)
+            let server = serve_fn(turbine_static, ServerOptions::default(), addr);
+            let background = turbine_static.run_background_tasks();
+            futures::pin_mut!(server, background);
+            futures::future::select(server, background).await;
             0
         }
         EngineOperation::Tinker => match turbine.tinker() {
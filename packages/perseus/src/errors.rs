@@ -286,6 +286,18 @@ pub enum ServerError {
         #[source]
         source: serde_json::Error,
     },
+    #[error("the index of pages incrementally generated for the template '{template_name}' in the mutable store was invalid (the store has been tampered with)")]
+    InvalidIncrementalIndex {
+        template_name: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("the index of pages and widgets using the cache tag '{tag}' in the mutable store was invalid (the store has been tampered with)")]
+    InvalidTagIndex {
+        tag: String,
+        #[source]
+        source: serde_json::Error,
+    },
 
     // `PathWithoutLocale`
     #[error("attempting to resolve dependency '{widget}' in locale '{locale}' produced a locale redirection verdict (this shouldn't be possible)")]
@@ -316,12 +328,20 @@ pub enum ServerError {
     // This can occur in state acquisition failures during prerendering
     #[error(transparent)]
     ClientError(#[from] ClientError),
+    // When several requests share the generation of a page, they all get the same error
+    #[error(transparent)]
+    Shared(std::sync::Arc<ServerError>),
 }
 /// Converts a server error into an HTTP status code.
 #[cfg(engine)]
 pub fn err_to_status_code(err: &ServerError) -> u16 {
     match err {
         ServerError::ServeError(ServeError::PageNotFound { .. }) => 404,
+        ServerError::ServeError(ServeError::CannotRevalidate { .. }) => 400,
+        ServerError::ServeError(ServeError::GuardDenied { status, .. }) => *status,
+        ServerError::ServeError(ServeError::StatusResponse { status, .. }) => *status,
+        ServerError::ServeError(ServeError::Redirect { status, .. }) => *status,
+        ServerError::Shared(err) => err_to_status_code(err),
         // Ambiguous (user-generated error), we'll rely on the given cause
         ServerError::RenderFnFailed { blame, .. } => match blame {
             ErrorBlame::Client(code) => code.unwrap_or(400),
@@ -331,6 +351,16 @@ pub fn err_to_status_code(err: &ServerError) -> u16 {
         _ => 500,
     }
 }
+/// Gets the destination and status code of the redirect a server error
+/// represents, if it represents one.
+#[cfg(engine)]
+pub(crate) fn err_to_redirect(err: &ServerError) -> Option<(&str, u16)> {
+    match err {
+        ServerError::ServeError(ServeError::Redirect { dest, status }) => Some((dest, *status)),
+        ServerError::Shared(err) => err_to_redirect(err),
+        _ => None,
+    }
+}
 
 /// Errors that can occur while reading from or writing to a mutable or
 /// immutable store.
@@ -351,6 +381,8 @@ pub enum StoreError {
         #[source]
         source: Box<dyn std::error::Error + Send + Sync>,
     },
+    #[error("store does not support the '{operation}' operation")]
+    NotSupported { operation: String },
 }
 
 /// Errors that can occur while fetching a resource from the server.
@@ -450,6 +482,29 @@ pub enum ServeError {
     PageNotFound { path: String },
     #[error("both build and request states were defined for a template when only one or fewer were expected (should it be able to amalgamate states?)")]
     BothStatesDefined,
+    #[error("page/widget at '{path}' can't be revalidated on-demand (only revalidating or incrementally generated pages/widgets can be)")]
+    CannotRevalidate { path: String },
+    #[error("request for page at '{path}' was denied by the guard of template '{template_name}'")]
+    GuardDenied {
+        path: String,
+        template_name: String,
+        status: u16,
+    },
+    #[error("render function '{fn_name}' in template '{template_name}' responded with status code {status}")]
+    StatusResponse {
+        fn_name: String,
+        template_name: String,
+        status: u16,
+    },
+    #[error("render function '{fn_name}' in template '{template_name}' tried to redirect with status code {status}, which isn't a redirection code (use 301, 302, 303, 307, or 308)")]
+    InvalidRedirectStatus {
+        fn_name: String,
+        template_name: String,
+        status: u16,
+    },
+    // This isn't really an error, but it needs to stop state generation in its tracks
+    #[error("request was redirected to '{dest}'")]
+    Redirect { dest: String, status: u16 },
     #[cfg(engine)]
     #[error("couldn't parse revalidation datetime (try cleaning all assets)")]
     BadRevalidate {
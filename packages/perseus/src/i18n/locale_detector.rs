@@ -1,6 +1,9 @@
 use super::Locales;
+#[cfg(any(client, doc))]
 use crate::{path::PathWithoutLocale, utils::get_path_prefix_client};
+#[cfg(any(client, doc))]
 use sycamore::rt::Reflect;
+#[cfg(any(client, doc))]
 use wasm_bindgen::JsValue;
 
 /// Detects which locale the user should be served and redirects appropriately.
@@ -12,39 +15,25 @@ use wasm_bindgen::JsValue;
 ///
 /// Note that this does not actually redirect on its own, it merely provides an
 /// argument for `sycamore_router::navigate_replace()`.
+#[cfg(any(client, doc))]
 pub(crate) fn detect_locale(url: PathWithoutLocale, locales: &Locales) -> String {
-    // If nothing matches, we'll use the default locale
-    let mut locale = locales.default.clone();
-
     // We'll use `navigator.languages` to figure out the best locale, falling back
     // to `navigator.language` if necessary
     let navigator = web_sys::window().unwrap().navigator();
-    let langs = navigator.languages().to_vec();
-    if langs.is_empty() {
+    let langs = navigator
+        .languages()
+        .to_vec()
+        .iter()
+        // We can reasonably assume that the user's locales are strings
+        .map(|lang| lang.as_string().unwrap())
+        .collect::<Vec<_>>();
+    let locale = if langs.is_empty() {
         // We'll fall back to `language`, which only gives us one locale to compare with
         // If that isn't supported, we'll automatically fall back to the default locale
-        if let Some(lang) = navigator.language() {
-            locale = match compare_locale(&lang, &locales.get_all()) {
-                LocaleMatch::Exact(matched) | LocaleMatch::Language(matched) => matched,
-                LocaleMatch::None => locales.default.to_string(),
-            }
-        }
+        match_preferred_locale(navigator.language(), locales)
     } else {
-        // We'll match each language individually, remembering that any exact match is
-        // preferable to a language-only match
-        for cmp in langs {
-            // We can reasonably assume that the user's locales are strings
-            let cmp_str = cmp.as_string().unwrap();
-            // As per RFC 4647, the first match (exact or language-only) is the one we'll
-            // use
-            if let LocaleMatch::Exact(matched) | LocaleMatch::Language(matched) =
-                compare_locale(&cmp_str, &locales.get_all())
-            {
-                locale = matched;
-                break;
-            }
-        }
-    }
+        match_preferred_locale(langs, locales)
+    };
 
     // Figure out what the new localized route should be
     // This is complex because we need to strip away the base path
@@ -73,6 +62,59 @@ pub(crate) fn detect_locale(url: PathWithoutLocale, locales: &Locales) -> String
     new_loc.to_string()
 }
 
+/// Detects which locale the user should be served from the value of the
+/// `Accept-Language` HTTP header on their request, using the same matching
+/// logic as the browser-side locale detection, so that the server can redirect
+/// clients without Wasm (e.g. crawlers) to the right place. The languages in
+/// the header will be tried in order of their quality values (any with a
+/// quality of zero, and the `*` wildcard, are ignored). If none of them are
+/// supported, this will return the default locale.
+#[cfg(engine)]
+pub(crate) fn detect_locale_from_header(accept_language: &str, locales: &Locales) -> String {
+    let mut langs = accept_language
+        .split(',')
+        .filter_map(|lang| {
+            let mut parts = lang.split(';').map(str::trim);
+            let lang = parts
+                .next()
+                .filter(|lang| !lang.is_empty() && *lang != "*")?;
+            // Anything without a valid quality value is treated as having the maximum
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (quality > 0.0).then_some((lang, quality))
+        })
+        .collect::<Vec<_>>();
+    // This is a stable sort, so languages with the same quality will stay in order
+    langs.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    match_preferred_locale(langs.into_iter().map(|(lang, _)| lang), locales)
+}
+
+/// Matches the given list of the user's preferred locales (most preferred
+/// first) against the locales the app supports. As per [RFC 4647](https://www.rfc-editor.org/rfc/rfc4647.txt),
+/// the first of the user's locales to match (exactly or language-only) is the
+/// one that will be used, and the default locale will be used if none of
+/// them do.
+fn match_preferred_locale<S: AsRef<str>>(
+    langs: impl IntoIterator<Item = S>,
+    locales: &Locales,
+) -> String {
+    // We'll match each language individually, remembering that any exact match is
+    // preferable to a language-only match
+    for lang in langs {
+        if let LocaleMatch::Exact(matched) | LocaleMatch::Language(matched) =
+            compare_locale(lang.as_ref(), &locales.get_all())
+        {
+            return matched;
+        }
+    }
+
+    // If nothing matches, we'll use the default locale
+    locales.default.to_string()
+}
+
 /// The possible outcomes of trying to match a locale.
 #[derive(Debug, PartialEq, Eq)]
 enum LocaleMatch {
@@ -148,4 +190,22 @@ mod tests {
         let verdict = compare_locale("en-US", &["en-GB", "en-US"]);
         assert_eq!(verdict, LocaleMatch::Exact("en-US".to_string()))
     }
+    #[test]
+    #[cfg(engine)]
+    fn detects_locale_from_header() {
+        let locales = Locales {
+            default: "en-US".to_string(),
+            other: vec!["fr-FR".to_string(), "de-DE".to_string()],
+            using_i18n: true,
+        };
+        assert_eq!(
+            detect_locale_from_header("de-CH;q=0.8, fr-FR;q=0.9, *;q=0.5", &locales),
+            "fr-FR"
+        );
+        assert_eq!(detect_locale_from_header("zh-CN", &locales), "en-US");
+        assert_eq!(
+            detect_locale_from_header("fr-FR;q=0, de", &locales),
+            "de-DE"
+        );
+    }
 }
@@ -3,7 +3,6 @@
 
 #[cfg(any(client, doc))]
 mod client_translations_manager;
-#[cfg(any(client, doc))]
 mod locale_detector;
 mod locales;
 mod translations_manager;
@@ -12,6 +11,8 @@ mod translations_manager;
 pub(crate) use client_translations_manager::ClientTranslationsManager;
 #[cfg(any(client, doc))]
 pub(crate) use locale_detector::detect_locale;
+#[cfg(engine)]
+pub(crate) use locale_detector::detect_locale_from_header;
 pub use locales::Locales;
 pub use translations_manager::{
     FsTranslationsManager, TranslationsManager, TranslationsManagerError,
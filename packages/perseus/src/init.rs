@@ -1,11 +1,14 @@
 #[cfg(engine)]
 use crate::server::HtmlShell;
 #[cfg(engine)]
+use crate::stores::FsImmutableStore;
+#[cfg(engine)]
 use crate::utils::get_path_prefix_server;
 use crate::{
     error_views::ErrorViews,
     i18n::{Locales, TranslationsManager},
     plugins::{PluginAction, Plugins},
+    router::{is_redirect_status, RouteRules},
     state::GlobalStateCreator,
     stores::MutableStore,
     template::{Entity, Forever, Template},
@@ -128,6 +131,8 @@ pub struct PerseusAppBase<G: Html, M: MutableStore, T: TranslationsManager> {
     pub(crate) global_state_creator: Arc<GlobalStateCreator>,
     /// The internationalization information for the app.
     pub(crate) locales: Locales,
+    /// The redirects and rewrites the app declares.
+    pub(crate) route_rules: RouteRules,
     /// The static aliases the app serves.
     #[cfg(engine)]
     pub(crate) static_aliases: HashMap<String, String>,
@@ -138,7 +143,7 @@ pub struct PerseusAppBase<G: Html, M: MutableStore, T: TranslationsManager> {
     pub(crate) plugins: Rc<Plugins>,
     /// The app's immutable store.
     #[cfg(engine)]
-    pub(crate) immutable_store: ImmutableStore,
+    pub(crate) immutable_store: Arc<dyn ImmutableStore>,
     /// The HTML template that'll be used to render the app into. This must be
     /// static, but can be generated or sourced in any way. Note that this MUST
     /// contain a `<div>` with the `id` set to whatever the value of `self.root`
@@ -159,6 +164,20 @@ pub struct PerseusAppBase<G: Html, M: MutableStore, T: TranslationsManager> {
     /// here will only be used if it exists.
     #[cfg(engine)]
     pub(crate) static_dir: String,
+    /// The maximum number of pages and widgets whose state will be generated
+    /// at once while building (and exporting) the app. By default, there is
+    /// no limit.
+    #[cfg(engine)]
+    pub(crate) build_concurrency: Option<usize>,
+    /// The maximum number of pages whose initial loads will be cached in
+    /// memory by the server. By default, nothing is cached.
+    #[cfg(engine)]
+    pub(crate) initial_load_cache: Option<usize>,
+    /// Whether or not the server should time each phase of handling requests
+    /// for pages, reporting the results in a `Server-Timing` header and
+    /// `tracing` spans. By default, this is disabled.
+    #[cfg(engine)]
+    pub(crate) server_timing: bool,
     /// A handler for panics on the browser-side.
     #[cfg(any(client, doc))]
     #[allow(clippy::type_complexity)] // TODO Really?
@@ -190,6 +209,7 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> std::fmt::Debug for Perse
             .field("error_views", &self.error_views)
             .field("pss_max_size", &self.pss_max_size)
             .field("locale", &self.locales)
+            .field("route_rules", &self.route_rules)
             .field("plugins", &self.plugins)
             .field("index_view", &self.index_view);
         #[cfg(any(client, doc))]
@@ -211,6 +231,9 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> std::fmt::Debug for Perse
                 .field("mutable_store", &self.mutable_store)
                 .field("translations_manager", &self.translations_manager)
                 .field("static_dir", &self.static_dir)
+                .field("build_concurrency", &self.build_concurrency)
+                .field("initial_load_cache", &self.initial_load_cache)
+                .field("server_timing", &self.server_timing)
                 .field("static_aliases", &self.static_aliases)
                 .field("immutable_store", &self.immutable_store)
                 .finish_non_exhaustive();
@@ -341,6 +364,7 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
             // building
             error_views: None,
             pss_max_size: DFLT_PSS_MAX_SIZE,
+            route_rules: RouteRules::default(),
             #[cfg(engine)]
             global_state_creator: Arc::new(GlobalStateCreator::default()),
             // By default, we'll disable i18n (as much as I may want more websites to support more
@@ -359,7 +383,7 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
             #[cfg(any(client, doc))]
             plugins: Rc::new(Plugins::new()),
             #[cfg(engine)]
-            immutable_store: ImmutableStore::new("./dist".to_string()),
+            immutable_store: Arc::new(FsImmutableStore::new("./dist".to_string())),
             #[cfg(engine)]
             mutable_store,
             #[cfg(engine)]
@@ -368,6 +392,12 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
             index_view: DFLT_INDEX_VIEW.to_string(),
             #[cfg(engine)]
             static_dir: "./static".to_string(),
+            #[cfg(engine)]
+            build_concurrency: None,
+            #[cfg(engine)]
+            initial_load_cache: None,
+            #[cfg(engine)]
+            server_timing: false,
             #[cfg(any(client, doc))]
             panic_handler: None,
             #[cfg(any(client, doc))]
@@ -390,6 +420,7 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
             // building
             error_views: None,
             pss_max_size: DFLT_PSS_MAX_SIZE,
+            route_rules: RouteRules::default(),
             // By default, we'll disable i18n (as much as I may want more websites to support more
             // languages...)
             locales: Locales {
@@ -430,6 +461,62 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
         }
         self
     }
+    /// Sets the maximum number of pages and widgets whose state will be
+    /// generated at once while building (and exporting) the app. By
+    /// default, every page is built at once, which can overwhelm any APIs
+    /// your state generation functions call, or run out of file descriptors,
+    /// if you have thousands of build paths. (Templates can also set their own
+    /// limits with `.build_concurrency()`, which will apply in addition to
+    /// this one.)
+    ///
+    /// This has no effect on request-time state generation.
+    #[allow(unused_variables)]
+    #[allow(unused_mut)]
+    pub fn build_concurrency(mut self, val: usize) -> Self {
+        #[cfg(engine)]
+        {
+            self.build_concurrency = Some(val);
+        }
+        self
+    }
+    /// Enables an in-memory cache of the full HTML responses the server gives
+    /// for initial loads, holding at most the given number of pages. Only
+    /// pages that can't change from request to request will be cached (i.e.
+    /// those that don't use request state or revalidation, and don't depend
+    /// on any widgets that do, in apps whose global state doesn't use
+    /// request state either). Since these pages are never regenerated at
+    /// request-time, they won't go stale while they're cached.
+    ///
+    /// This is disabled by default.
+    #[allow(unused_variables)]
+    #[allow(unused_mut)]
+    pub fn initial_load_cache(mut self, capacity: usize) -> Self {
+        #[cfg(engine)]
+        {
+            self.initial_load_cache = Some(capacity);
+        }
+        self
+    }
+    /// Enables timing of each phase of the server's handling of requests for
+    /// pages (e.g. generating the global state and request state, rendering
+    /// widgets, interpolating into the HTML shell, and minifying). The results
+    /// will be sent to the browser in a `Server-Timing` header, which can be
+    /// inspected in its development tools, and each phase will also be run
+    /// in a `tracing` span, which will be picked up by whatever subscriber
+    /// your server has set up.
+    ///
+    /// This is disabled by default, and it should generally be kept disabled
+    /// in production, since the `Server-Timing` header will be visible to
+    /// anyone who can load your pages.
+    #[allow(unused_variables)]
+    #[allow(unused_mut)]
+    pub fn server_timing(mut self, val: bool) -> Self {
+        #[cfg(engine)]
+        {
+            self.server_timing = val;
+        }
+        self
+    }
     /// Sets all the app's templates. This takes a vector of templates.
     ///
     /// Usually, it's preferred to run `.template()` once for each template,
@@ -693,13 +780,15 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
         self
     }
     /// Sets the [`ImmutableStore`] for the app to use. You should almost never
-    /// need to change this unless you're not working with the CLI.
+    /// need to change this unless you're not working with the CLI, or you want
+    /// to embed your build artifacts into the server binary with an
+    /// [`EmbeddedImmutableStore`](crate::stores::EmbeddedImmutableStore).
     #[allow(unused_variables)]
     #[allow(unused_mut)]
-    pub fn immutable_store(mut self, val: ImmutableStore) -> Self {
+    pub fn immutable_store(mut self, val: impl ImmutableStore + 'static) -> Self {
         #[cfg(engine)]
         {
-            self.immutable_store = val;
+            self.immutable_store = Arc::new(val);
         }
         self
     }
@@ -744,6 +833,49 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
         self.pss_max_size = val;
         self
     }
+    /// Adds a redirect from any path matching the given pattern to the given
+    /// destination, with the given HTTP status code (which should be one of
+    /// `301`, `302`, `303`, `307`, or `308`; use `308` for permanent
+    /// redirects). Redirects and rewrites are checked in the order they're
+    /// declared, before any routing takes place, on both the engine-side and
+    /// the browser-side.
+    ///
+    /// Patterns are matched against the whole path (without any path prefix,
+    /// but with the locale, if your app uses i18n), segment by segment. A
+    /// segment like `:slug` will match any single segment, and a final
+    /// segment like `*rest` will match everything after it. These can be
+    /// used in the destination too, so `.redirect("/blog/:slug",
+    /// "/posts/:slug", 308)` will redirect `/blog/hello` to `/posts/hello`.
+    /// The destination must be a path within your app.
+    ///
+    /// When your app is exported, redirects whose patterns don't use
+    /// parameters will be written as static pages that redirect the user as
+    /// soon as they're loaded (but they can't have a status code).
+    ///
+    /// # Panics
+    /// This will panic if the given status code isn't one of those above.
+    pub fn redirect(mut self, from: &str, to: &str, status: u16) -> Self {
+        if !is_redirect_status(status) {
+            panic!(
+                "the status code for the redirect from '{}' must be 301, 302, 303, 307, or 308, not {}",
+                from, status
+            );
+        }
+        self.route_rules.add_redirect(from, to, status);
+        self
+    }
+    /// Adds a rewrite, which will make any path matching the given pattern be
+    /// routed as if the user had requested the given destination instead,
+    /// without changing the URL they see. This uses the same pattern syntax
+    /// as `.redirect()`, and rewrites are checked in the same order as
+    /// redirects.
+    ///
+    /// Note that rewrites can't be represented in exported apps, so they'll
+    /// only apply to navigation within the app there.
+    pub fn rewrite(mut self, from: &str, to: &str) -> Self {
+        self.route_rules.add_rewrite(from, to);
+        self
+    }
     /// Sets the browser-side panic handler for your app. This is a function
     /// that will be executed if your app panics (which should never be caused
     /// by Perseus unless something is seriously wrong, it's much more likely
@@ -954,7 +1086,7 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> PerseusAppBase<G, M, T> {
     }
     /// Gets the [`ImmutableStore`].
     #[cfg(engine)]
-    pub fn get_immutable_store(&self) -> Result<ImmutableStore, PluginError> {
+    pub fn get_immutable_store(&self) -> Result<Arc<dyn ImmutableStore>, PluginError> {
         let immutable_store = self.immutable_store.clone();
         let immutable_store = self
             .plugins
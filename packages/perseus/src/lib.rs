@@ -11,6 +11,7 @@
 - `idb-freezing` --- enables utilities for freezing your app's state to IndexedDB in the browser (see the book)
 - `live-reload` (default) --- enables reloading the browser automatically when you make changes to your app
 - `hsr` (default) --- enables *hot state reloading*, which reloads the state of your app right before you made code changes in development, allowing you to pick up where you left off
+- `sqlite-store` --- enables `SqliteMutableStore`, a mutable store backed by a single SQLite database, which can be shared between multiple server processes
 
 ## Packages
 
@@ -144,6 +145,8 @@ pub mod prelude {
     pub use crate::reactor::Reactor;
     pub use crate::state::{BuildPaths, RxResult, RxResultRx, SerdeInfallible, StateGeneratorInfo};
     pub use crate::template::{Capsule, Template};
+    #[cfg(engine)]
+    pub use crate::template::{GuardVerdict, RequestOutcome};
     pub use sycamore::web::Html;
     pub use sycamore_router::{navigate, navigate_replace};
 
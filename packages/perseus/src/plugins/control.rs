@@ -107,8 +107,10 @@ pub struct ControlPluginActions {
 pub struct ControlPluginSettingsActions {
     /// Sets an immutable store to be used everywhere. This will provided the
     /// current immutable store for reference.
-    pub set_immutable_store:
-        ControlPluginAction<crate::stores::ImmutableStore, crate::stores::ImmutableStore>,
+    pub set_immutable_store: ControlPluginAction<
+        std::sync::Arc<dyn crate::stores::ImmutableStore>,
+        std::sync::Arc<dyn crate::stores::ImmutableStore>,
+    >,
     /// Sets the locales to be used everywhere, providing the current ones for
     /// reference.
     pub set_locales: ControlPluginAction<crate::i18n::Locales, crate::i18n::Locales>,
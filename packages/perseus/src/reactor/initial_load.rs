@@ -5,7 +5,7 @@ use crate::{
     errors::*,
     i18n::detect_locale,
     path::PathMaybeWithLocale,
-    router::{match_route, FullRouteInfo, FullRouteVerdict, RouterLoadState},
+    router::{match_route, FullRouteInfo, FullRouteVerdict, RouteRuleMatch, RouterLoadState},
     state::TemplateState,
     utils::{checkpoint, get_path_prefix_client},
 };
@@ -46,6 +46,13 @@ impl<G: Html> Reactor<G> {
             .map_err(|_| ClientPlatformError::InitialPath)?
             .as_string()
             .ok_or(ClientPlatformError::InitialPath)?;
+        // Redirects and rewrites take priority over everything else (though the server
+        // will usually have handled redirects already)
+        let path = match self.route_rules.resolve(&path, &path_prefix) {
+            Some(RouteRuleMatch::Redirect { dest, .. }) => return Ok(InitialView::Redirect(dest)),
+            Some(RouteRuleMatch::Rewrite(path)) => path,
+            None => path,
+        };
 
         // Start by figuring out what template we should be rendering
         let path_segments = path
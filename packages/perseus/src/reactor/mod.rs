@@ -42,7 +42,7 @@ use crate::{
     i18n::{ClientTranslationsManager, Locales, TranslationsManager},
     init::PerseusAppBase,
     plugins::PluginAction,
-    router::RouterState,
+    router::{RouteRules, RouterState},
     state::{FrozenApp, ThawPrefs},
     stores::MutableStore,
 };
@@ -105,6 +105,9 @@ pub struct Reactor<G: Html> {
     /// The app's locales.
     #[cfg(any(client, doc))]
     pub(crate) locales: Locales,
+    /// The app's redirects and rewrites.
+    #[cfg(any(client, doc))]
+    pub(crate) route_rules: RouteRules,
     /// The browser-side translations manager.
     #[cfg(any(client, doc))]
     translations_manager: ClientTranslationsManager,
@@ -192,6 +195,7 @@ impl<G: Html, M: MutableStore, T: TranslationsManager> TryFrom<PerseusAppBase<G,
             popup_error_view: create_rc_signal(View::empty()),
             entities: app.entities,
             locales,
+            route_rules: app.route_rules,
             render_cfg,
             #[cfg(debug_assertions)]
             error_views: app.error_views.unwrap_or_default(),
@@ -50,7 +50,7 @@ pub(crate) enum RenderMode<G: Html> {
         /// are not.
         widget_render_cfg: HashMap<String, String>,
         /// The app's immutable store. (This is cheap to clone.)
-        immutable_store: ImmutableStore,
+        immutable_store: Arc<dyn ImmutableStore>,
         /// An accumulator of the widget states involved in rendering this
         /// template. We need to be able to collect these to later send
         /// them to clients for hydration.
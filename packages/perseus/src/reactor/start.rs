@@ -369,6 +369,7 @@ impl Reactor<BrowserNodeType> {
                         // This is completely invalid, but will never be read
                         verdict: RouteVerdict::NotFound { locale: "xx-XX".to_string() },
                         cx: Some(cx),
+                        redirect: None,
                     },
                     view = move |cx, route: &ReadSignal<PerseusRoute>| {
                         // Do this on every update to the route, except the first time, when we'll use the initial load
@@ -385,6 +386,11 @@ impl Reactor<BrowserNodeType> {
                             } else {
                                 spawn_local_scoped(cx, async move {
                                     let route = route.get();
+                                    // Redirects don't need anything to be loaded
+                                    if let Some(dest) = &route.redirect {
+                                        navigate_replace(dest);
+                                        return;
+                                    }
                                     let verdict = route.get_verdict();
 
                                     // Get the subsequent view and handle errors
@@ -6,7 +6,7 @@ use sycamore::{
 };
 
 use crate::{
-    errors::{AssetType, ClientError, ClientInvariantError},
+    errors::{AssetType, ClientError, ClientInvariantError, FetchError},
     i18n::detect_locale,
     page_data::PageDataPartial,
     path::PathMaybeWithLocale,
@@ -55,8 +55,14 @@ impl<G: Html> Reactor<G> {
                 self.router_state.set_last_verdict(slim_verdict);
 
                 // Before we fetch anything, first check if there's an entry in the PSS already
-                // (if there is, we can avoid a network request)
-                let page_data = match self.state_store.contains(&full_path) {
+                // (if there is, we can avoid a network request), although guarded pages always
+                // have to be checked with the server
+                let pss_contains = if entity.uses_guard() {
+                    PssContains::None
+                } else {
+                    self.state_store.contains(&full_path)
+                };
+                let page_data = match pss_contains {
                     // We only have one part of the puzzle (or nothing at all), and no guarantee
                     // that the other doesn't exist, so we'll have to check with
                     // the server to be safe. Remember that this function
@@ -74,7 +80,19 @@ impl<G: Html> Reactor<G> {
                         // If this doesn't exist, then it's a 404 (we went here by explicit
                         // navigation, but it may be an unservable ISR page
                         // or the like)
-                        let page_data_str = fetch(&asset_url, AssetType::Page).await?;
+                        let page_data_str = match fetch(&asset_url, AssetType::Page).await {
+                            // The page's guard or state generation redirected us, and the body
+                            // is the destination
+                            Err(ClientError::FetchError(FetchError::NotOk {
+                                status,
+                                err: dest,
+                                ..
+                            })) if (300..400).contains(&status) => {
+                                sycamore_router::navigate_replace(&dest);
+                                return Ok((View::empty(), create_scope(|_| {})));
+                            }
+                            res => res?,
+                        };
                         match &page_data_str {
                             Some(page_data_str) => {
                                 // All good, deserialize the page data
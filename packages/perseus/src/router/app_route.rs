@@ -1,5 +1,5 @@
-use super::{match_route, RouteVerdict};
-use crate::{reactor::Reactor, template::BrowserNodeType};
+use super::{match_route, RouteRuleMatch, RouteVerdict};
+use crate::{reactor::Reactor, template::BrowserNodeType, utils::get_path_prefix_client};
 use sycamore::prelude::Scope;
 use sycamore_router::Route;
 
@@ -14,6 +14,9 @@ pub(crate) struct PerseusRoute<'cx> {
     ///
     /// This will *always* be `Some(_)` in actual applications.
     pub cx: Option<Scope<'cx>>,
+    /// The URL the user should be redirected to instead of loading anything,
+    /// if one of the app's redirects matched this route.
+    pub redirect: Option<String>,
 }
 // Sycamore would only use this if we were processing dynamic routes, which
 // we're not
@@ -27,6 +30,7 @@ impl<'cx> Default for PerseusRoute<'cx> {
             },
             // Again, this will never be accessed
             cx: None,
+            redirect: None,
         }
     }
 }
@@ -45,12 +49,31 @@ impl<'cx> Route for PerseusRoute<'cx> {
             .unwrap()
             .as_string()
             .unwrap();
+
+        let reactor = Reactor::<BrowserNodeType>::from_cx(self.cx.unwrap()); // We know the scope will always exist
+
+        // Redirects and rewrites take priority over everything else
+        let path = match reactor
+            .route_rules
+            .resolve(&path, &get_path_prefix_client())
+        {
+            Some(RouteRuleMatch::Redirect { dest, .. }) => {
+                return Self {
+                    // This won't be used
+                    verdict: RouteVerdict::NotFound {
+                        locale: "xx-XX".to_string(),
+                    },
+                    cx: self.cx,
+                    redirect: Some(dest),
+                };
+            }
+            Some(RouteRuleMatch::Rewrite(path)) => path,
+            None => path,
+        };
         let path_segments = path
             .split('/')
             .filter(|s| !s.is_empty())
             .collect::<Vec<&str>>(); // This parsing is identical to the Sycamore router's
-
-        let reactor = Reactor::<BrowserNodeType>::from_cx(self.cx.unwrap()); // We know the scope will always exist
         let verdict = match_route(
             &path_segments,
             &reactor.render_cfg,
@@ -60,6 +83,7 @@ impl<'cx> Route for PerseusRoute<'cx> {
         Self {
             verdict,
             cx: self.cx,
+            redirect: None,
         }
     }
 }
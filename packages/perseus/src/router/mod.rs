@@ -3,6 +3,7 @@ mod app_route;
 mod match_route;
 #[cfg(any(client, doc))]
 mod page_disposer;
+mod route_rules;
 mod route_verdict;
 #[cfg(any(client, doc))]
 mod router_state;
@@ -10,6 +11,9 @@ mod router_state;
 #[cfg(any(client, doc))]
 pub(crate) use app_route::PerseusRoute;
 pub(crate) use match_route::match_route;
+#[cfg(engine)]
+pub(crate) use route_rules::redirect_stub;
+pub(crate) use route_rules::{is_redirect_status, RouteRuleMatch, RouteRules};
 pub use route_verdict::{FullRouteInfo, FullRouteVerdict, RouteInfo, RouteVerdict};
 #[cfg(any(client, doc))]
 pub use router_state::{RouterLoadState, RouterState};
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+/// The redirects and rewrites declared on a `PerseusApp`, which are resolved
+/// (in the order they were declared) before the routing algorithm runs, on
+/// both the engine-side and the browser-side.
+///
+/// Patterns are matched against the path the user requested (without the
+/// path prefix, but *with* the locale, if there is one), segment by segment.
+/// A segment like `:slug` will match any single segment, and a final segment
+/// like `*rest` will match everything after it (including nothing). These can
+/// then be used in the same way in the destination, so a redirect from
+/// `/blog/:slug` to `/posts/:slug` would send `/blog/hello` to `/posts/hello`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RouteRules(Vec<RouteRule>);
+
+#[derive(Clone, Debug)]
+struct RouteRule {
+    from: String,
+    to: String,
+    /// The HTTP status code to redirect with, or `None` if this is a rewrite.
+    redirect_status: Option<u16>,
+}
+
+/// The outcome of one of an app's redirects or rewrites matching a path.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum RouteRuleMatch {
+    /// The user should be redirected to the given URL (which will include the
+    /// path prefix) with the given HTTP status code.
+    Redirect {
+        /// The URL to redirect to.
+        dest: String,
+        /// The status code to redirect with.
+        status: u16,
+    },
+    /// The given path should be routed instead of the one requested, without
+    /// the user's URL changing.
+    Rewrite(String),
+}
+
+impl RouteRules {
+    /// Adds a redirect.
+    pub(crate) fn add_redirect(&mut self, from: &str, to: &str, status: u16) {
+        self.0.push(RouteRule {
+            from: from.to_string(),
+            to: to.to_string(),
+            redirect_status: Some(status),
+        });
+    }
+    /// Adds a rewrite.
+    pub(crate) fn add_rewrite(&mut self, from: &str, to: &str) {
+        self.0.push(RouteRule {
+            from: from.to_string(),
+            to: to.to_string(),
+            redirect_status: None,
+        });
+    }
+    /// Resolves the given (URL-decoded) path against these rules, returning
+    /// the outcome of the first one that matches, if any do. The given path
+    /// prefix will be prepended to the destinations of redirects.
+    pub(crate) fn resolve(&self, path: &str, path_prefix: &str) -> Option<RouteRuleMatch> {
+        let segments = split_path(path);
+        self.0.iter().find_map(|rule| {
+            let params = match_pattern(&rule.from, &segments)?;
+            let dest = fill_pattern(&rule.to, &params);
+            Some(match rule.redirect_status {
+                Some(status) => RouteRuleMatch::Redirect {
+                    dest: format!("{}{}", path_prefix, dest),
+                    status,
+                },
+                None => RouteRuleMatch::Rewrite(dest),
+            })
+        })
+    }
+    /// Gets the sources and destinations of all the redirects whose patterns
+    /// don't have any parameters, which are the only ones that can be
+    /// represented by static files when exporting.
+    #[cfg(engine)]
+    pub(crate) fn static_redirects(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .filter(|rule| {
+                rule.redirect_status.is_some()
+                    && !split_path(&rule.from)
+                        .iter()
+                        .any(|segment| segment.starts_with(':') || segment.starts_with('*'))
+            })
+            .map(|rule| (rule.from.as_str(), rule.to.as_str()))
+    }
+}
+
+/// Checks whether or not the given HTTP status code is one that actually
+/// redirects the user somewhere else (which excludes codes like *304 Not
+/// Modified*).
+pub(crate) fn is_redirect_status(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Creates a tiny HTML page that will redirect the user to the given URL as
+/// soon as it's loaded, for use in exported apps, where we can't send real
+/// redirects.
+#[cfg(engine)]
+pub(crate) fn redirect_stub(dest: &str) -> String {
+    let attr = dest.replace('&', "&amp;").replace('"', "&quot;");
+    // This will escape everything appropriately for a JS string
+    let js = serde_json::to_string(dest).unwrap().replace('<', "\\u003c");
+    format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><meta http-equiv="refresh" content="0; url={attr}"><link rel="canonical" href="{attr}"><script>window.location.replace({js});</script></head><body><a href="{attr}">{attr}</a></body></html>"#
+    )
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Matches the given path segments against the given pattern, returning the
+/// values of its parameters if it matches.
+fn match_pattern<'a>(pattern: &'a str, segments: &[&str]) -> Option<HashMap<&'a str, String>> {
+    let pattern_segments = split_path(pattern);
+    let mut params = HashMap::new();
+    for (idx, pattern_segment) in pattern_segments.iter().enumerate() {
+        // Wildcards consume the rest of the path
+        if let Some(name) = pattern_segment.strip_prefix('*') {
+            params.insert(name, segments.get(idx..).unwrap_or_default().join("/"));
+            return Some(params);
+        }
+
+        let segment = segments.get(idx)?;
+        match pattern_segment.strip_prefix(':') {
+            Some(name) => {
+                params.insert(name, segment.to_string());
+            }
+            None if pattern_segment == segment => (),
+            None => return None,
+        }
+    }
+
+    (pattern_segments.len() == segments.len()).then_some(params)
+}
+
+/// Substitutes the given parameters into the given destination pattern,
+/// producing a path with a leading slash.
+fn fill_pattern(pattern: &str, params: &HashMap<&str, String>) -> String {
+    let path = split_path(pattern)
+        .into_iter()
+        .map(|segment| {
+            segment
+                .strip_prefix(':')
+                .or_else(|| segment.strip_prefix('*'))
+                .and_then(|name| params.get(name))
+                .map_or(segment, String::as_str)
+        })
+        // Empty wildcards shouldn't leave double slashes
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("/{}", path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_redirects_and_rewrites() {
+        let mut rules = RouteRules::default();
+        rules.add_redirect("/blog/:slug", "/posts/:slug", 308);
+        rules.add_rewrite("/docs/*rest", "/documentation/*rest");
+        rules.add_redirect("/old", "/new", 301);
+
+        assert_eq!(
+            rules.resolve("blog/hello", "/base"),
+            Some(RouteRuleMatch::Redirect {
+                dest: "/base/posts/hello".to_string(),
+                status: 308
+            })
+        );
+        assert_eq!(
+            rules.resolve("/docs/a/b/", ""),
+            Some(RouteRuleMatch::Rewrite("/documentation/a/b".to_string()))
+        );
+        assert_eq!(
+            rules.resolve("/docs", ""),
+            Some(RouteRuleMatch::Rewrite("/documentation".to_string()))
+        );
+        assert_eq!(rules.resolve("/blog/hello/world", ""), None);
+        assert_eq!(rules.resolve("/older", ""), None);
+        assert!(is_redirect_status(308));
+        assert!(!is_redirect_status(304));
+        assert_eq!(
+            rules.static_redirects().collect::<Vec<_>>(),
+            vec![("/old", "/new")]
+        );
+    }
+}
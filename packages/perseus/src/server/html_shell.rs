@@ -1,7 +1,7 @@
 use crate::error_views::ServerErrorData;
 use crate::page_data::PageData;
 use crate::state::TemplateState;
-use crate::utils::minify;
+use crate::utils::{minify, time_phase_sync};
 use std::{env, fmt};
 
 /// Escapes special characters in page data that might interfere with JavaScript
@@ -56,7 +56,6 @@ pub(crate) struct HtmlShell {
     /// The ID of the element into which we'll interpolate content.
     root_id: String,
     /// The path prefix to use.
-    #[cfg_attr(not(feature = "preload-wasm-on-redirect"), allow(dead_code))]
     path_prefix: String,
 }
 impl HtmlShell {
@@ -395,13 +394,51 @@ impl HtmlShell {
 
         self
     }
-}
-// This code actually interpolates everything in the correct places
-// Because of the way these string interpolations work, there MUST NOT be
-// hydration IDs on the `<head>` or `<body>` tags, or Perseus will break in very
-// unexpected ways
-impl fmt::Display for HtmlShell {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+    /// Preloads the JS and Wasm bundles at the very start of the document
+    /// head. This is used for streaming initial loads, where the start of
+    /// the head is sent before the page has been rendered, so that the
+    /// browser can start fetching the bundles immediately.
+    pub(crate) fn preload_bundle(mut self) -> Self {
+        let js_preload = format!(
+            r#"<link rel="modulepreload" href="{path_prefix}/.perseus/bundle.js" />"#,
+            path_prefix = self.path_prefix
+        );
+        let wasm_preload = format!(
+            r#"<link rel="preload" href="{path_prefix}/.perseus/bundle.wasm" as="fetch" crossorigin />"#,
+            path_prefix = self.path_prefix
+        );
+        self.head_before_boundary.push(js_preload);
+        self.head_before_boundary.push(wasm_preload);
+
+        self
+    }
+
+    /// Gets the start of this shell, up to and including the additional
+    /// contents of the head before the interpolation boundary. This depends
+    /// only on the shell itself and the locale, so it can be sent to the
+    /// client before the page has been rendered. Everything else can be
+    /// obtained later with `.rest()`.
+    ///
+    /// Unlike the `Display` implementation, this will not be minified, since
+    /// it isn't a complete document.
+    pub(crate) fn head_start(&self) -> String {
+        self.interpolate_parts().0
+    }
+    /// Gets the rest of this shell after `.head_start()`. As with that, this
+    /// will not be minified.
+    pub(crate) fn rest(&self) -> String {
+        self.interpolate_parts().1
+    }
+
+    /// This code actually interpolates everything in the correct places,
+    /// returning the start of the document up to the end of the contents of
+    /// the head before the interpolation boundary, and then everything else.
+    ///
+    /// Because of the way these string interpolations work, there MUST NOT be
+    /// hydration IDs on the `<head>` or `<body>` tags, or Perseus will break in
+    /// very unexpected ways
+    fn interpolate_parts(&self) -> (String, String) {
         let head_start = self.head_before_boundary.join("\n");
         // We also inject a delimiter dummy `<meta>` tag that will be used to wall off
         // the constant document head from the interpolated document head
@@ -421,8 +458,18 @@ impl fmt::Display for HtmlShell {
             scripts_after_boundary = self.scripts_after_boundary.join("\n"),
         );
 
-        let shell_with_head = self
-            .shell
+        // We split the shell just after the opening of the head, so that everything
+        // up to the end of `head_start` can be sent before anything else is known
+        let (shell_start, shell_rest) = match self.shell.find("<head>") {
+            Some(idx) => self.shell.split_at(idx + "<head>".len()),
+            None => ("", self.shell.as_str()),
+        };
+        let start = if shell_start.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", shell_start, head_start)
+        };
+        let shell_with_head = shell_rest
             .replace("<head>", &format!("<head>{}", head_start))
             .replace("</head>", &format!("{}</head>", head_end));
 
@@ -455,19 +502,29 @@ impl fmt::Display for HtmlShell {
             .replace(&html_to_replace_single, &html_replacement);
 
         // Finally, set the `lang` tag if we should
-        let final_shell = if self.locale != "xx-XX" {
-            new_shell.replace("<html", &format!(r#"<html lang="{}""#, self.locale))
+        if self.locale != "xx-XX" {
+            let lang = format!(r#"<html lang="{}""#, self.locale);
+            (
+                start.replace("<html", &lang),
+                new_shell.replace("<html", &lang),
+            )
         } else {
-            new_shell
-        };
+            (start, new_shell)
+        }
+    }
+}
+impl fmt::Display for HtmlShell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (start, rest) = time_phase_sync("shell", || self.interpolate_parts());
+        let final_shell = format!("{}{}", start, rest);
 
         // And minify everything
         // Because this is run on live requests, we have to be fault-tolerant (if we
         // can't minify, we'll fall back to unminified)
-        let minified = match minify(&final_shell, true) {
+        let minified = time_phase_sync("minify", || match minify(&final_shell, true) {
             Ok(minified) => minified,
             Err(_) => final_shell,
-        };
+        });
 
         f.write_str(&minified)
     }
@@ -1,3 +1,5 @@
+use crate::turbine::RevalidationSchedulerOptions;
+
 /// The options for setting up all server integrations. This should be literally
 /// constructed, as nothing is optional. If integrations need further
 /// properties, they should expose their own options in addition to these.
@@ -13,6 +15,26 @@ pub struct ServerOptions {
     pub wasm_js_bundle: String,
     /// The location of the JS interop snippets to be served as static files.
     pub snippets: String,
+    /// The secret that must be provided to the on-demand revalidation endpoint
+    /// at `/.perseus/revalidate` (as an `Authorization: Bearer <secret>`
+    /// header). If this is `None`, that endpoint will not be exposed at all.
+    pub revalidation_secret: Option<String>,
+    /// The options for the background revalidation scheduler, which
+    /// revalidates pages that use time-based revalidation as soon as they
+    /// expire, rather than waiting for them to be requested. If this is
+    /// `None`, the scheduler won't be run.
+    pub revalidation_scheduler: Option<RevalidationSchedulerOptions>,
+    /// Whether or not to stream initial loads to the client, sending the
+    /// start of the HTML shell (which includes preloads of the app's bundles)
+    /// before the page has been fully rendered (see
+    /// `Turbine::get_initial_load_stream`).
+    pub stream_initial_loads: bool,
+    /// The path under which health check endpoints will be exposed for
+    /// orchestrators: `<path>/live` for liveness probes, and `<path>/ready`
+    /// for readiness probes (see `Turbine::health`). If this is `None`, these
+    /// endpoints will not be exposed at all. They aren't authenticated, so
+    /// you should make sure only your orchestrator can reach them.
+    pub health_path: Option<String>,
 }
 #[cfg(feature = "dflt-engine")]
 impl Default for ServerOptions {
@@ -25,6 +47,28 @@ impl Default for ServerOptions {
             // browsers, we'll provide it anyway
             wasm_js_bundle: "dist/pkg/perseus_engine_bg.wasm.js".to_string(),
             snippets: "dist/pkg/snippets".to_string(),
+            // On-demand revalidation is only enabled if the user has explicitly set a secret
+            revalidation_secret: std::env::var("PERSEUS_REVALIDATION_SECRET").ok(),
+            // The scheduler is opt-in, through an interval in seconds (with an optional
+            // concurrency limit)
+            revalidation_scheduler: std::env::var("PERSEUS_REVALIDATION_SCHEDULER_INTERVAL")
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .map(|secs| {
+                    let dflt = RevalidationSchedulerOptions::default();
+                    RevalidationSchedulerOptions {
+                        interval: std::time::Duration::from_secs(secs),
+                        concurrency: std::env::var("PERSEUS_REVALIDATION_SCHEDULER_CONCURRENCY")
+                            .ok()
+                            .and_then(|limit| limit.parse().ok())
+                            .unwrap_or(dflt.concurrency),
+                    }
+                }),
+            stream_initial_loads: std::env::var("PERSEUS_STREAM_INITIAL_LOADS")
+                .map(|val| matches!(val.trim().to_lowercase().as_str(), "true" | "1"))
+                .unwrap_or(false),
+            // Health checks are opt-in, since they expose internal state to anyone
+            health_path: std::env::var("PERSEUS_HEALTH_PATH").ok(),
         }
     }
 }
@@ -18,6 +18,8 @@ pub(crate) use global_state::FrozenGlobalState;
 pub use global_state::{GlobalState, GlobalStateCreator, GlobalStateType};
 pub use rx_result::{RxResult, RxResultRx, SerdeInfallible};
 pub use rx_state::{AnyFreeze, Freeze, MakeRx, MakeUnrx, UnreactiveState};
+#[cfg(engine)]
+pub(crate) use state_generator_info::CacheTags;
 pub use state_generator_info::{BuildPaths, StateGeneratorInfo};
 pub use state_store::{PageStateStore, PssContains, PssEntry, PssState};
 pub use template_state::{TemplateState, TemplateStateWithType, UnknownStateType};
@@ -1,5 +1,6 @@
 use super::{TemplateState, TemplateStateWithType};
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::{Arc, Mutex};
 
 /// The output of the build seed system, which should be generated by a user
 /// function for each template.
@@ -32,6 +33,8 @@ pub struct StateGeneratorInfo<B: Serialize + DeserializeOwned + Send + Sync> {
     pub locale: String,
     /// Any extra data from the template's build seed.
     pub(crate) extra: TemplateStateWithType<B>,
+    /// The cache tags the state generator has attached to its output.
+    pub(crate) cache_tags: CacheTags,
 }
 impl<B: Serialize + DeserializeOwned + Send + Sync + 'static> StateGeneratorInfo<B> {
     /// Transform the underlying [`TemplateStateWithType`] into one with a
@@ -45,8 +48,23 @@ impl<B: Serialize + DeserializeOwned + Send + Sync + 'static> StateGeneratorInfo
             path: self.path,
             locale: self.locale,
             extra: self.extra.change_type(),
+            cache_tags: self.cache_tags,
         }
     }
+    /// Attaches the given cache tag to the state being generated, which
+    /// declares that it depends on some shared data source, like a
+    /// particular product in a store. Then, when that data changes, you can
+    /// call `Turbine::invalidate_tag()` to mark every page and widget whose
+    /// state was generated with this tag as needing revalidation.
+    ///
+    /// This only has an effect in build state functions for templates and
+    /// capsules whose pages/widgets are stored in the mutable store (i.e.
+    /// those that revalidate, or that are generated incrementally), since
+    /// nothing else can be revalidated. Request state is generated fresh
+    /// for every request, so tagging it is pointless.
+    pub fn add_cache_tag(&self, tag: impl Into<String>) {
+        self.cache_tags.add(tag.into());
+    }
     /// Get the extra build state as an owned type.
     ///
     /// # Panics
@@ -67,3 +85,22 @@ impl<B: Serialize + DeserializeOwned + Send + Sync + 'static> StateGeneratorInfo
         }
     }
 }
+
+/// A shared list of cache tags, which state generators can add to (through
+/// [`StateGeneratorInfo`]) while the engine keeps its own handle.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CacheTags(Arc<Mutex<Vec<String>>>);
+impl CacheTags {
+    /// Adds a tag, if it hasn't already been added.
+    fn add(&self, tag: String) {
+        let mut tags = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    /// Takes all the tags that have been added so far.
+    #[cfg(engine)]
+    pub(crate) fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+}
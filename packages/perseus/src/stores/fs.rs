@@ -0,0 +1,126 @@
+use crate::errors::*;
+use futures::lock::Mutex as AsyncMutex;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+use tokio::{
+    fs::{create_dir_all, remove_file, rename, File},
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+
+/// The extension given to temporary files while they're being written. Store
+/// listings should ignore any files ending in this.
+pub(crate) const TMP_EXTENSION: &str = ".perseus-tmp";
+
+/// A counter used to make sure temporary file names are unique within this
+/// process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A set of locks, one for each asset currently being written, which prevents
+/// concurrent writes to the same asset (e.g. from two simultaneous
+/// revalidations of the same page) from interleaving. Clones share the same
+/// set of locks.
+///
+/// Locks are only held by writers: readers never observe partial writes anyway,
+/// since assets are replaced with an atomic rename.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct KeyLocks {
+    // We hold weak references so that locks for assets nobody is writing any more
+    // can be cleaned up
+    locks: Arc<Mutex<HashMap<String, Weak<AsyncMutex<()>>>>>,
+}
+impl KeyLocks {
+    /// Gets the lock for the given key, creating it if necessary.
+    pub(crate) fn get(&self, key: &str) -> Arc<AsyncMutex<()>> {
+        let mut locks = self.locks.lock().unwrap();
+        if let Some(lock) = locks.get(key).and_then(Weak::upgrade) {
+            return lock;
+        }
+        // Take the opportunity to get rid of any locks that are no longer in use
+        locks.retain(|_, lock| lock.strong_count() > 0);
+        let lock = Arc::new(AsyncMutex::new(()));
+        locks.insert(key.to_string(), Arc::downgrade(&lock));
+
+        lock
+    }
+}
+
+/// Reads the raw contents of the file at the given path, returning
+/// `StoreError::NotFound` if it doesn't exist.
+pub(crate) async fn read_file(asset_path: &str) -> Result<Vec<u8>, StoreError> {
+    let mut file = match File::open(asset_path).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(StoreError::NotFound {
+                name: asset_path.to_string(),
+            })
+        }
+        Err(err) => {
+            return Err(StoreError::ReadFailed {
+                name: asset_path.to_string(),
+                source: err.into(),
+            })
+        }
+    };
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)
+        .await
+        .map_err(|err| StoreError::ReadFailed {
+            name: asset_path.to_string(),
+            source: err.into(),
+        })?;
+
+    Ok(contents)
+}
+
+/// Writes the given contents to the given path atomically, by writing them to a
+/// temporary file in the same directory first and then renaming that over the
+/// target. This means concurrent readers will see either the old contents or
+/// the new contents, never a partial write, and a crash halfway through will
+/// leave the old contents intact. Any missing parent directories will be
+/// created.
+///
+/// This doesn't do any locking, callers should hold the lock for the asset from
+/// [`KeyLocks`].
+pub(crate) async fn write_atomic(asset_path: &str, content: &[u8]) -> Result<(), StoreError> {
+    let write_err = |err: std::io::Error| StoreError::WriteFailed {
+        name: asset_path.to_string(),
+        source: err.into(),
+    };
+
+    let mut dir_tree: Vec<&str> = asset_path.split('/').collect();
+    dir_tree.pop();
+    create_dir_all(dir_tree.join("/"))
+        .await
+        .map_err(write_err)?;
+
+    // This has to be in the same directory as the target for the rename to be atomic
+    let tmp_path = format!(
+        "{}.{}.{}{}",
+        asset_path,
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+        TMP_EXTENSION
+    );
+    let res = async {
+        let mut file = File::create(&tmp_path).await?;
+        file.write_all(content).await?;
+        // The data must be on disk before the rename, otherwise a crash could leave us
+        // with an empty file
+        file.sync_all().await?;
+        rename(&tmp_path, asset_path).await
+    }
+    .await;
+    if let Err(err) = res {
+        // Don't leave a stray temporary file around (there's nothing useful we can do if
+        // this fails)
+        let _ = remove_file(&tmp_path).await;
+        return Err(write_err(err));
+    }
+
+    Ok(())
+}
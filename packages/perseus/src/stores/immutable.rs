@@ -1,122 +1,236 @@
+use super::bytes_to_string;
 #[cfg(engine)]
+use super::fs::{read_file, write_atomic, KeyLocks};
 use crate::errors::*;
+use std::collections::HashMap;
 #[cfg(engine)]
-use tokio::{
-    fs::{create_dir_all, File},
-    io::{AsyncReadExt, AsyncWriteExt},
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
 };
 
-/// An immutable storage system used by Perseus' engine to store build artifacts
-/// and the like, which will then be used by the server or the export process.
-/// By default, this is set to a path inside the `dist/` folder at the root of
-/// your project, which you should only change if you have special requirements,
-/// as the CLI expects the default paths to be used, with no option for
-/// customization yet.
+/// A trait for implementations of stores that the Perseus engine can use for
+/// immutable data, which will never change after build-time. This is used by
+/// the engine to store build artifacts and the like, which will then be used
+/// by the server or the export process. By default, [`FsImmutableStore`] is
+/// used, with a path inside the `dist/` folder at the root of your project,
+/// which you should only change if you have special requirements, as the CLI
+/// expects the default paths to be used, with no option for customization
+/// yet.
 ///
 /// Note that this is only used for immutable data, which can be read-only in
 /// production, meaning there are no consequences of using this on a read-only
 /// production filesystem (e.g. in a serverless function). Data that do need to
 /// change use a [`MutableStore`](super::MutableStore) instead.
+///
+/// Like the mutable store, this is a trait so that you can write your own
+/// implementations of it, although there is much less reason to do so. If you
+/// want to deploy your app as a single binary, take a look at
+/// [`EmbeddedImmutableStore`].
+///
+/// **Warning:** the `NotFound` error is integral to Perseus' internal
+/// operation, and must be returned if an asset does not exist.
+#[async_trait::async_trait]
+pub trait ImmutableStore: std::fmt::Debug + Send + Sync {
+    /// Reads the raw bytes of the named asset.
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError>;
+    /// Writes the given raw bytes to the named asset. This must only be used at
+    /// build-time, and must not be changed afterward.
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError>;
+    /// Gets the filesystem path used for this immutable store.
+    ///
+    /// This is designed to be used in particular by the engine to work out
+    /// where to put static assets and the like when exporting.
+    fn get_path(&self) -> &str;
+    /// Reads the named asset as a string. This is a wrapper over
+    /// `.read_bytes()`.
+    async fn read(&self, name: &str) -> Result<String, StoreError> {
+        let contents = self.read_bytes(name).await?;
+        bytes_to_string(name, contents)
+    }
+    /// Writes the given string to the named asset. This is a wrapper over
+    /// `.write_bytes()`.
+    async fn write(&self, name: &str, content: &str) -> Result<(), StoreError> {
+        self.write_bytes(name, content.as_bytes()).await
+    }
+}
+
+/// The default immutable store, which uses the filesystem. The CLI will
+/// set this up for you at `dist/`.
+///
+/// Note: writes on this implementation will create any missing parent
+/// directories automatically, and are atomic, so a concurrent read will never
+/// see a partially written asset.
 #[derive(Clone, Debug)]
-pub struct ImmutableStore {
+pub struct FsImmutableStore {
     #[cfg(engine)]
     root_path: String,
+    #[cfg(engine)]
+    locks: KeyLocks,
 }
-impl ImmutableStore {
+#[cfg(engine)]
+impl FsImmutableStore {
     /// Creates a new immutable store. You should provide a path like `dist`
     /// here. Note that any trailing slashes will be automatically stripped.
-    #[cfg(engine)]
     pub fn new(root_path: String) -> Self {
         let root_path = root_path
             .strip_prefix('/')
             .unwrap_or(&root_path)
             .to_string();
-        Self { root_path }
+        Self {
+            root_path,
+            locks: KeyLocks::default(),
+        }
     }
-    /// Gets the filesystem path used for this immutable store.
-    ///
-    /// This is designed to be used in particular by the engine to work out
-    /// where to put static assets and the like when exporting.
+}
+#[async_trait::async_trait]
+impl ImmutableStore for FsImmutableStore {
     #[cfg(engine)]
-    pub fn get_path(&self) -> &str {
-        &self.root_path
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        let asset_path = format!("{}/{}", self.root_path, name);
+        read_file(&asset_path).await
     }
-    /// Reads the given asset from the filesystem asynchronously.
     #[cfg(engine)]
-    pub async fn read(&self, name: &str) -> Result<String, StoreError> {
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
         let asset_path = format!("{}/{}", self.root_path, name);
-        let file_res = File::open(&asset_path).await;
-        let mut file = match file_res {
-            Ok(file) => file,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                return Err(StoreError::NotFound { name: asset_path })
-            }
-            Err(err) => {
-                return Err(StoreError::ReadFailed {
-                    name: asset_path,
-                    source: err.into(),
-                })
-            }
-        };
-        let metadata = file.metadata().await;
+        let lock = self.locks.get(&asset_path);
+        let _guard = lock.lock().await;
+
+        write_atomic(&asset_path, content).await
+    }
+    #[cfg(engine)]
+    fn get_path(&self) -> &str {
+        &self.root_path
+    }
+    #[cfg(client)]
+    async fn read_bytes(&self, _name: &str) -> Result<Vec<u8>, StoreError> {
+        Ok(Vec::new())
+    }
+    #[cfg(client)]
+    async fn write_bytes(&self, _name: &str, _content: &[u8]) -> Result<(), StoreError> {
+        Ok(())
+    }
+    #[cfg(client)]
+    fn get_path(&self) -> &str {
+        ""
+    }
+}
 
-        match metadata {
-            Ok(_) => {
-                let mut contents = String::new();
-                file.read_to_string(&mut contents)
-                    .await
-                    .map_err(|err| StoreError::ReadFailed {
-                        name: asset_path,
-                        source: err.into(),
-                    })?;
-                Ok(contents)
-            }
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                Err(StoreError::NotFound { name: asset_path })
-            }
-            Err(err) => Err(StoreError::ReadFailed {
-                name: asset_path,
-                source: err.into(),
+/// An immutable store that serves build artifacts (`render_conf.json` and
+/// everything in `static/`) that have been embedded into the engine binary at
+/// compile-time, which allows deploying an app as a single file. You should
+/// create this with the `include_dist!()` macro, which will embed the
+/// artifacts from your `dist/` directory, so you'll need to run `perseus build`
+/// before compiling the binary you're going to deploy. Translations and your
+/// own static assets are not embedded.
+///
+/// Since the same engine binary is used to build your app as to serve it, this
+/// store is backed by an [`FsImmutableStore`] (by default at `./dist`, as with
+/// the default store), to which all writes go. Where reads come from is decided
+/// once, when the store is created: if `render_conf.json` exists in the
+/// filesystem store (i.e. there's a complete build on disk), everything is read
+/// from there, so that a fresh build always takes precedence over whatever was
+/// embedded last time; otherwise, everything is served from the embedded
+/// artifacts (unless nothing was embedded at all). Once anything has been
+/// written (i.e. during a build), reads will go to the filesystem.
+#[derive(Clone)]
+pub struct EmbeddedImmutableStore {
+    files: HashMap<&'static str, &'static [u8]>,
+    #[cfg(engine)]
+    fallback: FsImmutableStore,
+    /// Whether or not reads should go to the filesystem rather than the
+    /// embedded artifacts. This is shared between clones so that a write
+    /// through any of them will be visible to reads through all of them.
+    #[cfg(engine)]
+    use_fs: Arc<AtomicBool>,
+}
+impl std::fmt::Debug for EmbeddedImmutableStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Printing the contents of every file would be less than helpful
+        let mut debug = f.debug_struct("EmbeddedImmutableStore");
+        debug.field("files", &self.files.len());
+        #[cfg(engine)]
+        debug.field("fallback", &self.fallback);
+        #[cfg(engine)]
+        debug.field("use_fs", &self.use_fs.load(Ordering::Relaxed));
+        debug.finish()
+    }
+}
+impl EmbeddedImmutableStore {
+    /// Creates a new embedded store from the given list of asset names (e.g.
+    /// `static/global_state.json`) and their contents. You should generally
+    /// use the `include_dist!()` macro rather than calling this directly.
+    pub fn new(files: &'static [(&'static str, &'static [u8])]) -> Self {
+        #[cfg(engine)]
+        let fallback = FsImmutableStore::new("./dist".to_string());
+        Self {
+            files: files.iter().copied().collect(),
+            #[cfg(engine)]
+            use_fs: Arc::new(AtomicBool::new(
+                files.is_empty() || Self::has_build(&fallback),
+            )),
+            #[cfg(engine)]
+            fallback,
+        }
+    }
+    /// Sets the filesystem store that will be used for writes, and for reads
+    /// if it contains a complete build. You only need to change this if you've
+    /// changed the default immutable store path.
+    #[cfg(engine)]
+    pub fn fallback(mut self, val: FsImmutableStore) -> Self {
+        self.use_fs = Arc::new(AtomicBool::new(
+            self.files.is_empty() || Self::has_build(&val),
+        ));
+        self.fallback = val;
+        self
+    }
+    /// Checks whether or not the given filesystem store contains a complete
+    /// build, which is indicated by the presence of `render_conf.json` (the
+    /// last thing the build process writes).
+    #[cfg(engine)]
+    fn has_build(store: &FsImmutableStore) -> bool {
+        std::path::Path::new(&format!("{}/render_conf.json", store.get_path())).exists()
+    }
+}
+#[async_trait::async_trait]
+impl ImmutableStore for EmbeddedImmutableStore {
+    #[cfg(engine)]
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        if self.use_fs.load(Ordering::Acquire) {
+            return self.fallback.read_bytes(name).await;
+        }
+        match self.files.get(name) {
+            Some(contents) => Ok(contents.to_vec()),
+            None => Err(StoreError::NotFound {
+                name: name.to_string(),
             }),
         }
     }
-    /// Writes the given asset to the filesystem asynchronously. This must only
-    /// be used at build-time, and must not be changed afterward. Note that this
-    /// will automatically create any missing parent directories.
     #[cfg(engine)]
-    pub async fn write(&self, name: &str, content: &str) -> Result<(), StoreError> {
-        let asset_path = format!("{}/{}", self.root_path, name);
-        let mut dir_tree: Vec<&str> = asset_path.split('/').collect();
-        dir_tree.pop();
-
-        create_dir_all(dir_tree.join("/"))
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path.clone(),
-                source: err.into(),
-            })?;
-
-        // This will either create the file or truncate it if it already exists
-        let mut file = File::create(&asset_path)
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path.clone(),
-                source: err.into(),
-            })?;
-        file.write_all(content.as_bytes())
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path.clone(),
-                source: err.into(),
-            })?;
-        // TODO Can we use `sync_data()` here to reduce I/O?
-        file.sync_all()
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path,
-                source: err.into(),
-            })?;
-
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        // Anything written from here on should be read back from the filesystem
+        self.use_fs.store(true, Ordering::Release);
+        self.fallback.write_bytes(name, content).await
+    }
+    #[cfg(engine)]
+    fn get_path(&self) -> &str {
+        self.fallback.get_path()
+    }
+    #[cfg(client)]
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        match self.files.get(name) {
+            Some(contents) => Ok(contents.to_vec()),
+            None => Err(StoreError::NotFound {
+                name: name.to_string(),
+            }),
+        }
+    }
+    #[cfg(client)]
+    async fn write_bytes(&self, _name: &str, _content: &[u8]) -> Result<(), StoreError> {
         Ok(())
     }
+    #[cfg(client)]
+    fn get_path(&self) -> &str {
+        ""
+    }
 }
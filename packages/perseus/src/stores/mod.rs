@@ -1,7 +1,28 @@
+/// Filesystem utilities shared by the default stores.
+#[cfg(engine)]
+mod fs;
 /// Utilities for working with immutable stores.
 pub mod immutable;
 /// Utilities for working with mutable stores.
 pub mod mutable;
+/// A mutable store backed by SQLite.
+#[cfg(all(engine, feature = "sqlite-store"))]
+pub mod sqlite;
 
-pub use immutable::ImmutableStore;
-pub use mutable::{FsMutableStore, MutableStore};
+pub use immutable::{EmbeddedImmutableStore, FsImmutableStore, ImmutableStore};
+pub use mutable::{
+    FsMutableStore, MemoryMutableStore, MutableStore, StoreMetadata, TieredMutableStore,
+};
+#[cfg(all(engine, feature = "sqlite-store"))]
+pub use sqlite::SqliteMutableStore;
+
+use crate::errors::StoreError;
+
+/// Converts the raw contents of an asset into a string, for the string-based
+/// wrappers over the byte-based store methods.
+fn bytes_to_string(name: &str, bytes: Vec<u8>) -> Result<String, StoreError> {
+    String::from_utf8(bytes).map_err(|err| StoreError::ReadFailed {
+        name: name.to_string(),
+        source: err.into(),
+    })
+}
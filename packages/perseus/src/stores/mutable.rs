@@ -1,9 +1,14 @@
-use crate::errors::*;
+use super::bytes_to_string;
 #[cfg(engine)]
-use tokio::{
-    fs::{create_dir_all, File},
-    io::{AsyncReadExt, AsyncWriteExt},
+use super::fs::{read_file, write_atomic, KeyLocks, TMP_EXTENSION};
+use crate::errors::*;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::SystemTime,
 };
+#[cfg(engine)]
+use tokio::fs::{read_dir, remove_file};
 
 /// A trait for implementations of stores that the Perseus engine can use for
 /// mutable data, which may need to be altered while the server is running. In
@@ -32,6 +37,76 @@ pub trait MutableStore: std::fmt::Debug + Clone + Send + Sync {
     /// Writes data to the named asset. This will create a new asset if one
     /// doesn't exist already.
     async fn write(&self, name: &str, content: &str) -> Result<(), StoreError>;
+    /// Reads the raw bytes of the named asset, which need not be valid UTF-8.
+    ///
+    /// By default, this wraps `.read()`, which means it can't handle non-UTF-8
+    /// data. Implementations that can store arbitrary bytes should override
+    /// this and `.write_bytes()`, and can then implement `.read()` and
+    /// `.write()` as thin wrappers over them.
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        self.read(name).await.map(String::into_bytes)
+    }
+    /// Writes raw bytes to the named asset, creating it if it doesn't exist
+    /// already.
+    ///
+    /// By default, this wraps `.write()`, and will fail if the given data are
+    /// not valid UTF-8.
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        let content = std::str::from_utf8(content).map_err(|err| StoreError::WriteFailed {
+            name: name.to_string(),
+            source: err.into(),
+        })?;
+        self.write(name, content).await
+    }
+    /// Deletes the named asset. Deleting an asset that doesn't exist is not an
+    /// error.
+    ///
+    /// By default, this returns `StoreError::NotSupported`.
+    async fn delete(&self, name: &str) -> Result<(), StoreError> {
+        let _ = name;
+        Err(StoreError::NotSupported {
+            operation: "delete".to_string(),
+        })
+    }
+    /// Checks whether or not the named asset exists.
+    ///
+    /// By default, this performs a full read of the asset, so implementations
+    /// should override this if they can do it more cheaply.
+    async fn exists(&self, name: &str) -> Result<bool, StoreError> {
+        match self.read(name).await {
+            Ok(_) => Ok(true),
+            Err(StoreError::NotFound { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+    /// Lists the names of all the assets in the store that start with the
+    /// given prefix (e.g. `static/en-US-`). Use an empty prefix to list
+    /// everything. No particular ordering is guaranteed.
+    ///
+    /// By default, this returns `StoreError::NotSupported`.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StoreError> {
+        let _ = prefix;
+        Err(StoreError::NotSupported {
+            operation: "list".to_string(),
+        })
+    }
+    /// Gets metadata about the named asset without reading it. This must
+    /// return `StoreError::NotFound` if the asset doesn't exist.
+    ///
+    /// By default, this returns `StoreError::NotSupported`.
+    async fn metadata(&self, name: &str) -> Result<StoreMetadata, StoreError> {
+        let _ = name;
+        Err(StoreError::NotSupported {
+            operation: "metadata".to_string(),
+        })
+    }
+}
+
+/// Metadata about an asset in a [`MutableStore`].
+#[derive(Clone, Debug)]
+pub struct StoreMetadata {
+    /// When the asset was last written to.
+    pub last_written: SystemTime,
 }
 
 /// The default [`MutableStore`], which simply uses the filesystem. This is
@@ -41,11 +116,15 @@ pub trait MutableStore: std::fmt::Debug + Clone + Send + Sync {
 /// [`MutableStore`] should be preferred.
 ///
 /// Note: the `.write()` methods on this implementation will create any missing
-/// parent directories automatically.
+/// parent directories automatically. Writes go to a temporary file that is then
+/// renamed over the asset, so readers will never see a partially written asset,
+/// and concurrent writes to the same asset are serialized.
 #[derive(Clone, Debug)]
 pub struct FsMutableStore {
     #[cfg(engine)]
     root_path: String,
+    #[cfg(engine)]
+    locks: KeyLocks,
 }
 #[cfg(engine)]
 impl FsMutableStore {
@@ -55,17 +134,113 @@ impl FsMutableStore {
     /// cause potentially problematic overlap between the two systems.
     #[cfg(engine)]
     pub fn new(root_path: String) -> Self {
-        Self { root_path }
+        Self {
+            root_path,
+            locks: KeyLocks::default(),
+        }
     }
 }
 #[async_trait::async_trait]
 impl MutableStore for FsMutableStore {
     #[cfg(engine)]
     async fn read(&self, name: &str) -> Result<String, StoreError> {
+        let contents = self.read_bytes(name).await?;
+        bytes_to_string(name, contents)
+    }
+    #[cfg(engine)]
+    async fn write(&self, name: &str, content: &str) -> Result<(), StoreError> {
+        self.write_bytes(name, content.as_bytes()).await
+    }
+    #[cfg(engine)]
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        let asset_path = format!("{}/{}", self.root_path, name);
+        read_file(&asset_path).await
+    }
+    // This creates a directory structure as necessary
+    #[cfg(engine)]
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        let asset_path = format!("{}/{}", self.root_path, name);
+        let lock = self.locks.get(&asset_path);
+        let _guard = lock.lock().await;
+
+        write_atomic(&asset_path, content).await
+    }
+    #[cfg(engine)]
+    async fn delete(&self, name: &str) -> Result<(), StoreError> {
+        let asset_path = format!("{}/{}", self.root_path, name);
+        let lock = self.locks.get(&asset_path);
+        let _guard = lock.lock().await;
+
+        match remove_file(&asset_path).await {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(StoreError::WriteFailed {
+                name: asset_path,
+                source: err.into(),
+            }),
+        }
+    }
+    #[cfg(engine)]
+    async fn exists(&self, name: &str) -> Result<bool, StoreError> {
+        match self.metadata(name).await {
+            Ok(_) => Ok(true),
+            Err(StoreError::NotFound { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+    #[cfg(engine)]
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StoreError> {
+        // We only need to walk the directory the prefix is in (e.g. `static/` for
+        // `static/en-US-`), since names map directly to paths
+        let start_dir = match prefix.rfind('/') {
+            Some(idx) => &prefix[..idx],
+            None => "",
+        };
+        let mut names = Vec::new();
+        let mut dirs = vec![start_dir.to_string()];
+        while let Some(dir) = dirs.pop() {
+            let dir_path = if dir.is_empty() {
+                self.root_path.clone()
+            } else {
+                format!("{}/{}", self.root_path, dir)
+            };
+            let mut entries = match read_dir(&dir_path).await {
+                Ok(entries) => entries,
+                // Nothing has been written under this prefix yet
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(StoreError::ReadFailed {
+                        name: dir_path,
+                        source: err.into(),
+                    })
+                }
+            };
+            let read_err = |err: std::io::Error| StoreError::ReadFailed {
+                name: dir_path.clone(),
+                source: err.into(),
+            };
+            while let Some(entry) = entries.next_entry().await.map_err(read_err)? {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let name = if dir.is_empty() {
+                    file_name
+                } else {
+                    format!("{}/{}", dir, file_name)
+                };
+                if entry.file_type().await.map_err(read_err)?.is_dir() {
+                    dirs.push(name);
+                } else if name.starts_with(prefix) && !name.ends_with(TMP_EXTENSION) {
+                    names.push(name);
+                }
+            }
+        }
+
+        Ok(names)
+    }
+    #[cfg(engine)]
+    async fn metadata(&self, name: &str) -> Result<StoreMetadata, StoreError> {
         let asset_path = format!("{}/{}", self.root_path, name);
-        let file_res = File::open(&asset_path).await;
-        let mut file = match file_res {
-            Ok(file) => file,
+        let metadata = match tokio::fs::metadata(&asset_path).await {
+            Ok(metadata) => metadata,
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
                 return Err(StoreError::NotFound { name: asset_path })
             }
@@ -76,71 +251,438 @@ impl MutableStore for FsMutableStore {
                 })
             }
         };
-        let metadata = file.metadata().await;
-
-        match metadata {
-            Ok(_) => {
-                let mut contents = String::new();
-                file.read_to_string(&mut contents)
-                    .await
-                    .map_err(|err| StoreError::ReadFailed {
-                        name: asset_path,
-                        source: err.into(),
-                    })?;
-                Ok(contents)
-            }
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                Err(StoreError::NotFound { name: asset_path })
+        let last_written = metadata.modified().map_err(|err| StoreError::ReadFailed {
+            name: asset_path,
+            source: err.into(),
+        })?;
+
+        Ok(StoreMetadata { last_written })
+    }
+    #[cfg(client)]
+    async fn read(&self, _name: &str) -> Result<String, StoreError> {
+        Ok(String::new())
+    }
+    #[cfg(client)]
+    async fn write(&self, _name: &str, _content: &str) -> Result<(), StoreError> {
+        Ok(())
+    }
+}
+
+/// A [`MutableStore`] that keeps everything in memory, evicting the least
+/// recently used assets once a configurable number of entries or bytes has
+/// been exceeded. This is useful for deployments with read-only or slow
+/// filesystems, and for test suites that shouldn't touch `dist/mutable`.
+///
+/// Note that nothing in this store survives a restart of the server process,
+/// and that includes anything written during `perseus build` (which runs in a
+/// separate process). Perseus will regenerate any revalidating or
+/// incrementally generated pages whose artifacts are missing, so this only
+/// means that the first request for each such page after startup (or after
+/// an eviction) will be slower.
+///
+/// By default, this store is unbounded. Use `.max_entries()` and
+/// `.max_bytes()` to set limits on it. All the assets for a single page/widget
+/// are evicted together, since none of them are useful without the others, and
+/// the page/widget most recently written to is never evicted, so the store may
+/// briefly exceed its limits if they're smaller than a single page/widget.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryMutableStore {
+    inner: Arc<Mutex<MemoryStoreInner>>,
+}
+impl MemoryMutableStore {
+    /// Creates a new, empty, unbounded in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets the maximum number of assets this store will hold before evicting
+    /// the least recently used ones.
+    pub fn max_entries(self, val: usize) -> Self {
+        self.inner.lock().unwrap().max_entries = Some(val);
+        self
+    }
+    /// Sets the maximum total size (in bytes) of the assets this store will
+    /// hold before evicting the least recently used ones. Writing a single
+    /// asset larger than this will fail.
+    pub fn max_bytes(self, val: usize) -> Self {
+        self.inner.lock().unwrap().max_bytes = Some(val);
+        self
+    }
+}
+#[async_trait::async_trait]
+impl MutableStore for MemoryMutableStore {
+    async fn read(&self, name: &str) -> Result<String, StoreError> {
+        let contents = self.read_bytes(name).await?;
+        bytes_to_string(name, contents)
+    }
+    async fn write(&self, name: &str, content: &str) -> Result<(), StoreError> {
+        self.write_bytes(name, content.as_bytes()).await
+    }
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.get(name).ok_or_else(|| StoreError::NotFound {
+            name: name.to_string(),
+        })
+    }
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(max_bytes) = inner.max_bytes {
+            if content.len() > max_bytes {
+                return Err(StoreError::WriteFailed {
+                    name: name.to_string(),
+                    source: format!(
+                        "asset of {} bytes exceeds the store's maximum size of {} bytes",
+                        content.len(),
+                        max_bytes
+                    )
+                    .into(),
+                });
             }
-            Err(err) => Err(StoreError::ReadFailed {
-                name: asset_path,
-                source: err.into(),
+        }
+        inner.insert(name, content.to_vec());
+
+        Ok(())
+    }
+    async fn delete(&self, name: &str) -> Result<(), StoreError> {
+        self.inner.lock().unwrap().remove(name);
+        Ok(())
+    }
+    async fn exists(&self, name: &str) -> Result<bool, StoreError> {
+        // This deliberately doesn't count as a use of the asset
+        Ok(self.inner.lock().unwrap().entries.contains_key(name))
+    }
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StoreError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .entries
+            .keys()
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+    async fn metadata(&self, name: &str) -> Result<StoreMetadata, StoreError> {
+        let inner = self.inner.lock().unwrap();
+        match inner.entries.get(name) {
+            Some(entry) => Ok(StoreMetadata {
+                last_written: entry.last_written,
+            }),
+            None => Err(StoreError::NotFound {
+                name: name.to_string(),
             }),
         }
     }
-    // This creates a directory structure as necessary
-    #[cfg(engine)]
+}
+
+/// A [`MutableStore`] that layers a fast store (e.g. a [`MemoryMutableStore`])
+/// in front of a slower one (e.g. an [`FsMutableStore`]). Reads will try the
+/// fast store first, falling back to the slow one on a miss, and will then
+/// backfill the fast store with whatever they found. Writes go through to both
+/// stores.
+///
+/// The slow store is treated as the source of truth: listings and metadata
+/// come from it, and a write that fails on the slow store will fail outright.
+/// If a write succeeds on the slow store but fails on the fast one (e.g.
+/// because the asset was too large for a bounded memory store), the asset will
+/// be removed from the fast store instead, so that it can't serve stale data.
+///
+/// Note that the fast store will only be kept consistent with changes made
+/// through this store, so the slow store shouldn't be shared with other
+/// processes that write to it.
+#[derive(Clone, Debug)]
+pub struct TieredMutableStore<A: MutableStore, B: MutableStore> {
+    fast: A,
+    slow: B,
+}
+impl<A: MutableStore, B: MutableStore> TieredMutableStore<A, B> {
+    /// Creates a new tiered store, with the given fast and slow stores.
+    pub fn new(fast: A, slow: B) -> Self {
+        Self { fast, slow }
+    }
+}
+#[async_trait::async_trait]
+impl<A: MutableStore, B: MutableStore> MutableStore for TieredMutableStore<A, B> {
+    async fn read(&self, name: &str) -> Result<String, StoreError> {
+        let contents = self.read_bytes(name).await?;
+        bytes_to_string(name, contents)
+    }
     async fn write(&self, name: &str, content: &str) -> Result<(), StoreError> {
-        let asset_path = format!("{}/{}", self.root_path, name);
-        let mut dir_tree: Vec<&str> = asset_path.split('/').collect();
-        dir_tree.pop();
+        self.write_bytes(name, content.as_bytes()).await
+    }
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        match self.fast.read_bytes(name).await {
+            Err(StoreError::NotFound { .. }) => {
+                let contents = self.slow.read_bytes(name).await?;
+                // A failed backfill just means the next read will miss again
+                let _ = self.fast.write_bytes(name, &contents).await;
+                Ok(contents)
+            }
+            res => res,
+        }
+    }
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        self.slow.write_bytes(name, content).await?;
+        if let Err(err) = self.fast.write_bytes(name, content).await {
+            // The fast store might still hold the old version of this asset
+            self.fast.delete(name).await.map_err(|_| err)?;
+        }
 
-        create_dir_all(dir_tree.join("/"))
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path.clone(),
-                source: err.into(),
-            })?;
+        Ok(())
+    }
+    async fn delete(&self, name: &str) -> Result<(), StoreError> {
+        self.slow.delete(name).await?;
+        self.fast.delete(name).await
+    }
+    async fn exists(&self, name: &str) -> Result<bool, StoreError> {
+        if self.fast.exists(name).await? {
+            Ok(true)
+        } else {
+            self.slow.exists(name).await
+        }
+    }
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StoreError> {
+        self.slow.list(prefix).await
+    }
+    async fn metadata(&self, name: &str) -> Result<StoreMetadata, StoreError> {
+        self.slow.metadata(name).await
+    }
+}
 
-        // This will either create the file or truncate it if it already exists
-        let mut file = File::create(&asset_path)
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path.clone(),
-                source: err.into(),
-            })?;
-        file.write_all(content.as_bytes())
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path.clone(),
-                source: err.into(),
-            })?;
-        // TODO Can we use `sync_data()` here to reduce I/O?
-        file.sync_all()
-            .await
-            .map_err(|err| StoreError::WriteFailed {
-                name: asset_path,
-                source: err.into(),
-            })?;
+/// The internals of a [`MemoryMutableStore`], which are kept behind a lock.
+#[derive(Debug, Default)]
+struct MemoryStoreInner {
+    /// The assets themselves.
+    entries: HashMap<String, MemoryStoreEntry>,
+    /// The groups of assets that are used and evicted together (see
+    /// [`asset_group`]), with the names of the assets in each and the tick at
+    /// which each was last used.
+    groups: HashMap<String, (HashSet<String>, u64)>,
+    /// The names of the groups, ordered by the tick at which they were last
+    /// used (so the first one is the least recently used).
+    recency: BTreeMap<u64, String>,
+    /// A monotonically increasing counter used to order accesses.
+    tick: u64,
+    /// The total size of all the assets currently held.
+    size: usize,
+    max_entries: Option<usize>,
+    max_bytes: Option<usize>,
+}
+/// A single asset in a [`MemoryMutableStore`].
+#[derive(Debug)]
+struct MemoryStoreEntry {
+    content: Vec<u8>,
+    last_written: SystemTime,
+}
+impl MemoryStoreInner {
+    /// Gets the given asset, marking its group as the most recently used.
+    fn get(&mut self, name: &str) -> Option<Vec<u8>> {
+        let content = self.entries.get(name)?.content.clone();
+        self.touch(asset_group(name));
 
-        Ok(())
+        Some(content)
     }
-    #[cfg(client)]
-    async fn read(&self, _name: &str) -> Result<String, StoreError> {
-        Ok(String::new())
+    /// Inserts the given asset, replacing any previous version, and then
+    /// evicts the least recently used groups of assets until the store is
+    /// back within its limits. The group of the asset just inserted is never
+    /// evicted, since the rest of it is about to be needed.
+    fn insert(&mut self, name: &str, content: Vec<u8>) {
+        self.size += content.len();
+        let entry = MemoryStoreEntry {
+            content,
+            last_written: SystemTime::now(),
+        };
+        if let Some(old) = self.entries.insert(name.to_string(), entry) {
+            self.size -= old.content.len();
+        }
+        let group = asset_group(name);
+        self.groups
+            .entry(group.to_string())
+            .or_default()
+            .0
+            .insert(name.to_string());
+        self.touch(group);
+
+        while self.max_entries.is_some_and(|max| self.entries.len() > max)
+            || self.max_bytes.is_some_and(|max| self.size > max)
+        {
+            let lru_group = match self.recency.first_key_value() {
+                Some((_, lru_group)) if lru_group != group => lru_group.clone(),
+                _ => break,
+            };
+            self.remove_group(&lru_group);
+        }
     }
-    #[cfg(client)]
-    async fn write(&self, _name: &str, _content: &str) -> Result<(), StoreError> {
-        Ok(())
+    /// Removes the given asset, if it exists.
+    fn remove(&mut self, name: &str) {
+        if let Some(old) = self.entries.remove(name) {
+            self.size -= old.content.len();
+            let group = asset_group(name);
+            if let Some((names, last_used)) = self.groups.get_mut(group) {
+                names.remove(name);
+                if names.is_empty() {
+                    self.recency.remove(last_used);
+                    self.groups.remove(group);
+                }
+            }
+        }
+    }
+    /// Marks the given group as the most recently used.
+    fn touch(&mut self, group: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, last_used)) = self.groups.get_mut(group) {
+            self.recency.remove(last_used);
+            *last_used = tick;
+            self.recency.insert(tick, group.to_string());
+        }
+    }
+    /// Removes every asset in the given group.
+    fn remove_group(&mut self, group: &str) {
+        if let Some((names, last_used)) = self.groups.remove(group) {
+            self.recency.remove(&last_used);
+            for name in names {
+                if let Some(old) = self.entries.remove(&name) {
+                    self.size -= old.content.len();
+                }
+            }
+        }
+    }
+}
+
+/// Gets the group the given asset belongs to in a [`MemoryMutableStore`].
+/// Perseus stores each page/widget as several assets under
+/// `static/{locale}-{path}` (its state, head, prerendered HTML, etc.), and
+/// serving it needs all of them, so these are grouped together. Any other
+/// asset is in a group of its own.
+fn asset_group(name: &str) -> &str {
+    if !name.starts_with("static/") {
+        return name;
+    }
+    // Longer suffixes come first, since some of these end with others
+    [
+        ".widgets.json",
+        ".invalidated.txt",
+        ".head.html",
+        ".revld.txt",
+        ".html",
+        ".json",
+    ]
+    .iter()
+    .find_map(|suffix| name.strip_suffix(suffix))
+    .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn memory_store_evicts_lru_entry() {
+        let store = MemoryMutableStore::new().max_entries(2);
+        block_on(async {
+            store.write("a", "1").await.unwrap();
+            store.write("b", "2").await.unwrap();
+            // Reading `a` makes `b` the least recently used
+            store.read("a").await.unwrap();
+            store.write("c", "3").await.unwrap();
+
+            assert_eq!(store.read("a").await.unwrap(), "1");
+            assert!(matches!(
+                store.read("b").await,
+                Err(StoreError::NotFound { .. })
+            ));
+            assert_eq!(store.read("c").await.unwrap(), "3");
+        });
+    }
+    #[test]
+    fn memory_store_evicts_page_assets_together() {
+        let store = MemoryMutableStore::new().max_entries(2);
+        block_on(async {
+            store.write("static/en-US-a.json", "1").await.unwrap();
+            store.write("static/en-US-a.head.html", "2").await.unwrap();
+            // The page being written is never evicted, even over the limit
+            store.write("static/en-US-a.html", "3").await.unwrap();
+            assert!(store.exists("static/en-US-a.json").await.unwrap());
+            store.write("static/en-US-b.json", "4").await.unwrap();
+
+            assert!(!store.exists("static/en-US-a.json").await.unwrap());
+            assert!(!store.exists("static/en-US-a.head.html").await.unwrap());
+            assert!(!store.exists("static/en-US-a.html").await.unwrap());
+            assert_eq!(store.read("static/en-US-b.json").await.unwrap(), "4");
+        });
+    }
+    #[test]
+    fn memory_store_respects_byte_limit() {
+        let store = MemoryMutableStore::new().max_bytes(4);
+        block_on(async {
+            store.write("a", "12").await.unwrap();
+            store.write("b", "34").await.unwrap();
+            // Overwriting shouldn't count the old contents
+            store.write("b", "56").await.unwrap();
+            store.write("c", "7").await.unwrap();
+
+            assert!(matches!(
+                store.read("a").await,
+                Err(StoreError::NotFound { .. })
+            ));
+            assert_eq!(store.read("b").await.unwrap(), "56");
+            assert!(store.write("d", "12345").await.is_err());
+        });
+    }
+    #[test]
+    fn memory_store_lists_and_deletes() {
+        let store = MemoryMutableStore::new();
+        block_on(async {
+            store.write("static/en-US-a.json", "1").await.unwrap();
+            store.write("static/en-US-b.json", "2").await.unwrap();
+            store.write("static/fr-FR-a.json", "3").await.unwrap();
+
+            let mut names = store.list("static/en-US-").await.unwrap();
+            names.sort();
+            assert_eq!(names, vec!["static/en-US-a.json", "static/en-US-b.json"]);
+
+            store.delete("static/en-US-a.json").await.unwrap();
+            // Deleting something that's already gone is fine
+            store.delete("static/en-US-a.json").await.unwrap();
+            assert!(!store.exists("static/en-US-a.json").await.unwrap());
+            assert!(store.exists("static/en-US-b.json").await.unwrap());
+            assert!(store.metadata("static/en-US-b.json").await.is_ok());
+            assert!(matches!(
+                store.metadata("static/en-US-a.json").await,
+                Err(StoreError::NotFound { .. })
+            ));
+        });
+    }
+    #[test]
+    fn memory_store_handles_binary_assets() {
+        let store = MemoryMutableStore::new();
+        block_on(async {
+            store.write_bytes("a.br", &[0xff, 0x00]).await.unwrap();
+
+            assert_eq!(store.read_bytes("a.br").await.unwrap(), vec![0xff, 0x00]);
+            assert!(matches!(
+                store.read("a.br").await,
+                Err(StoreError::ReadFailed { .. })
+            ));
+        });
+    }
+    #[test]
+    fn tiered_store_backfills_fast_tier() {
+        let fast = MemoryMutableStore::new().max_bytes(4);
+        let slow = MemoryMutableStore::new();
+        let store = TieredMutableStore::new(fast.clone(), slow.clone());
+        block_on(async {
+            slow.write("a", "1").await.unwrap();
+            assert_eq!(store.read("a").await.unwrap(), "1");
+            assert_eq!(fast.read("a").await.unwrap(), "1");
+
+            store.write("b", "2").await.unwrap();
+            assert_eq!(fast.read("b").await.unwrap(), "2");
+            assert_eq!(slow.read("b").await.unwrap(), "2");
+
+            // Too big for the fast tier, so the old version must be dropped from it
+            store.write("b", "34567").await.unwrap();
+            assert!(!fast.exists("b").await.unwrap());
+            assert_eq!(store.read("b").await.unwrap(), "34567");
+        });
     }
 }
@@ -0,0 +1,234 @@
+use super::{bytes_to_string, MutableStore, StoreMetadata};
+use crate::errors::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How long we'll wait for another process to release its lock on the database
+/// before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A [`MutableStore`] that keeps everything in a single SQLite database. Unlike
+/// [`FsMutableStore`](super::FsMutableStore), this can safely be shared between
+/// multiple server processes (e.g. on a shared volume), since every write is
+/// transactional. Having everything in one file also makes the mutable store
+/// easy to back up.
+///
+/// All database operations are run on Tokio's blocking thread pool, so this
+/// must be used within a Tokio runtime (which all the default integrations
+/// provide).
+///
+/// This requires the `sqlite-store` feature.
+#[derive(Clone)]
+pub struct SqliteMutableStore {
+    conn: Arc<Mutex<Connection>>,
+}
+impl std::fmt::Debug for SqliteMutableStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteMutableStore").finish_non_exhaustive()
+    }
+}
+impl SqliteMutableStore {
+    /// Opens the SQLite database at the given path (e.g.
+    /// `dist/mutable.sqlite`), creating it if it doesn't exist already.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, rusqlite::Error> {
+        let conn = Connection::open(path)?;
+        // Write-ahead logging lets readers in other processes keep going while we write
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::from_conn(conn)
+    }
+    /// Creates a new store in a private, in-memory database. This is mainly
+    /// useful for testing.
+    pub fn new_in_memory() -> Result<Self, rusqlite::Error> {
+        Self::from_conn(Connection::open_in_memory()?)
+    }
+    /// Sets up the schema on the given connection, if it isn't already there.
+    fn from_conn(conn: Connection) -> Result<Self, rusqlite::Error> {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS perseus_mutable (
+                name TEXT PRIMARY KEY NOT NULL,
+                content BLOB NOT NULL,
+                last_written INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+    /// Runs the given closure with the database connection on Tokio's blocking
+    /// thread pool. Any errors will be attributed to the given asset name,
+    /// and converted into the error type for the given operation.
+    async fn with_conn<R: Send + 'static>(
+        &self,
+        name: &str,
+        is_write: bool,
+        f: impl FnOnce(&mut Connection) -> Result<R, rusqlite::Error> + Send + 'static,
+    ) -> Result<R, StoreError> {
+        let conn = self.conn.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            // If another operation panicked, the connection itself is still fine
+            let mut conn = conn.lock().unwrap_or_else(|err| err.into_inner());
+            f(&mut conn)
+        })
+        .await;
+
+        let err: Box<dyn std::error::Error + Send + Sync> = match res {
+            Ok(Ok(val)) => return Ok(val),
+            Ok(Err(err)) => err.into(),
+            Err(err) => err.into(),
+        };
+        let name = name.to_string();
+        if is_write {
+            Err(StoreError::WriteFailed { name, source: err })
+        } else {
+            Err(StoreError::ReadFailed { name, source: err })
+        }
+    }
+}
+#[async_trait::async_trait]
+impl MutableStore for SqliteMutableStore {
+    async fn read(&self, name: &str) -> Result<String, StoreError> {
+        let contents = self.read_bytes(name).await?;
+        bytes_to_string(name, contents)
+    }
+    async fn write(&self, name: &str, content: &str) -> Result<(), StoreError> {
+        self.write_bytes(name, content.as_bytes()).await
+    }
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        let key = name.to_string();
+        let contents = self
+            .with_conn(name, false, move |conn| {
+                conn.query_row(
+                    "SELECT content FROM perseus_mutable WHERE name = ?1",
+                    params![key],
+                    |row| row.get::<_, Vec<u8>>(0),
+                )
+                .optional()
+            })
+            .await?;
+
+        contents.ok_or_else(|| StoreError::NotFound {
+            name: name.to_string(),
+        })
+    }
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        let key = name.to_string();
+        let content = content.to_vec();
+        let last_written = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        self.with_conn(name, true, move |conn| {
+            conn.execute(
+                "INSERT INTO perseus_mutable (name, content, last_written) VALUES (?1, ?2, ?3)
+                ON CONFLICT (name) DO UPDATE SET content = excluded.content, last_written = excluded.last_written",
+                params![key, content, last_written],
+            )
+        })
+        .await?;
+
+        Ok(())
+    }
+    async fn delete(&self, name: &str) -> Result<(), StoreError> {
+        let key = name.to_string();
+        self.with_conn(name, true, move |conn| {
+            conn.execute("DELETE FROM perseus_mutable WHERE name = ?1", params![key])
+        })
+        .await?;
+
+        Ok(())
+    }
+    async fn exists(&self, name: &str) -> Result<bool, StoreError> {
+        let key = name.to_string();
+        self.with_conn(name, false, move |conn| {
+            conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM perseus_mutable WHERE name = ?1)",
+                params![key],
+                |row| row.get(0),
+            )
+        })
+        .await
+    }
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StoreError> {
+        let key = prefix.to_string();
+        self.with_conn(prefix, false, move |conn| {
+            // This is a range query so that it can use the primary key index (`char(0x10FFFF)`
+            // sorts after everything else that could follow the prefix)
+            let mut stmt = conn.prepare_cached(
+                "SELECT name FROM perseus_mutable WHERE name >= ?1 AND name < ?1 || char(1114111)",
+            )?;
+            let names = stmt
+                .query_map(params![key], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            Ok(names)
+        })
+        .await
+    }
+    async fn metadata(&self, name: &str) -> Result<StoreMetadata, StoreError> {
+        let key = name.to_string();
+        let last_written = self
+            .with_conn(name, false, move |conn| {
+                conn.query_row(
+                    "SELECT last_written FROM perseus_mutable WHERE name = ?1",
+                    params![key],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()
+            })
+            .await?;
+
+        match last_written {
+            Some(millis) => Ok(StoreMetadata {
+                last_written: UNIX_EPOCH + Duration::from_millis(millis as u64),
+            }),
+            None => Err(StoreError::NotFound {
+                name: name.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlite_store_round_trips_assets() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let store = SqliteMutableStore::new_in_memory().unwrap();
+            store.write("static/en-US-a.json", "1").await.unwrap();
+            store.write("static/en-US-a.json", "2").await.unwrap();
+            store
+                .write_bytes("static/en-US-b.br", &[0xff])
+                .await
+                .unwrap();
+            store.write("static/fr-FR-a.json", "3").await.unwrap();
+
+            assert_eq!(store.read("static/en-US-a.json").await.unwrap(), "2");
+            assert_eq!(
+                store.read_bytes("static/en-US-b.br").await.unwrap(),
+                vec![0xff]
+            );
+            let mut names = store.list("static/en-US-").await.unwrap();
+            names.sort();
+            assert_eq!(names, vec!["static/en-US-a.json", "static/en-US-b.br"]);
+            assert!(store.metadata("static/en-US-a.json").await.is_ok());
+
+            store.delete("static/en-US-a.json").await.unwrap();
+            assert!(!store.exists("static/en-US-a.json").await.unwrap());
+            assert!(matches!(
+                store.read("static/en-US-a.json").await,
+                Err(StoreError::NotFound { .. })
+            ));
+        });
+    }
+}
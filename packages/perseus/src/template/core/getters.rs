@@ -1,6 +1,8 @@
 use super::TemplateInner;
 #[cfg(engine)]
 use crate::utils::ComputedDuration;
+#[cfg(engine)]
+use chrono::{DateTime, Utc};
 use sycamore::web::Html;
 
 impl<G: Html> TemplateInner<G> {
@@ -23,23 +25,60 @@ impl<G: Html> TemplateInner<G> {
             base
         }
     }
+    /// Checks if this template has a guard that must approve requests for its
+    /// pages.
+    pub fn uses_guard(&self) -> bool {
+        self.is_guarded
+    }
     /// Gets the interval after which the template will next revalidate.
     #[cfg(engine)]
     pub fn get_revalidate_interval(&self) -> Option<ComputedDuration> {
         self.revalidate_after.clone()
     }
+    /// Computes the timestamp (RFC 3339) at which the template should next
+    /// revalidate, from its revalidation interval and/or schedule (whichever
+    /// comes first). This will be `None` if the template doesn't revalidate
+    /// based on time.
+    #[cfg(engine)]
+    pub(crate) fn compute_revalidation_timestamp(&self) -> Option<String> {
+        let after = self
+            .revalidate_after
+            .as_ref()
+            .and_then(|interval| DateTime::parse_from_rfc3339(&interval.compute_timestamp()).ok())
+            .map(|time| time.with_timezone(&Utc));
+        let at = self
+            .revalidate_at
+            .as_ref()
+            .map(|schedule| schedule.next_after(&Utc::now()));
+        let next = match (after, at) {
+            (Some(after), Some(Some(at))) => Some(after.min(at)),
+            (Some(after), _) => Some(after),
+            (None, Some(at)) => Some(at.unwrap_or_else(|| {
+                // If the schedule has run out of times, this should never revalidate
+                // again (but a missing timestamp would trigger revalidation)
+                DateTime::parse_from_rfc3339("9999-12-31T23:59:59+00:00")
+                    .unwrap()
+                    .with_timezone(&Utc)
+            })),
+            (None, None) => None,
+        };
+
+        next.map(|time| time.to_rfc3339())
+    }
 
     // Render characteristic checkers
     /// Checks if this template can revalidate existing prerendered templates.
     #[cfg(engine)]
     pub fn revalidates(&self) -> bool {
-        self.should_revalidate.is_some() || self.revalidate_after.is_some()
+        self.should_revalidate.is_some()
+            || self.revalidate_after.is_some()
+            || self.revalidate_at.is_some()
     }
     /// Checks if this template can revalidate existing prerendered templates
     /// after a given time.
     #[cfg(engine)]
     pub fn revalidates_with_time(&self) -> bool {
-        self.revalidate_after.is_some()
+        self.revalidate_after.is_some() || self.revalidate_at.is_some()
     }
     /// Checks if this template can revalidate existing prerendered templates
     /// based on some given logic.
@@ -47,12 +86,36 @@ impl<G: Html> TemplateInner<G> {
     pub fn revalidates_with_logic(&self) -> bool {
         self.should_revalidate.is_some()
     }
+    /// Checks if this template's revalidations should be run in the
+    /// background, rather than at request-time.
+    #[cfg(engine)]
+    pub fn revalidates_in_background(&self) -> bool {
+        self.revalidate_in_background
+    }
     /// Checks if this template can render more templates beyond those paths it
     /// explicitly defines.
     #[cfg(engine)]
     pub fn uses_incremental(&self) -> bool {
         self.incremental_generation
     }
+    /// Checks if this template has custom logic to validate paths before they
+    /// are incrementally generated.
+    #[cfg(engine)]
+    pub fn uses_incremental_path_validator(&self) -> bool {
+        self.incremental_path_validator.is_some()
+    }
+    /// Gets the maximum number of pages that can be incrementally generated
+    /// for this template, if there is one.
+    #[cfg(engine)]
+    pub fn get_max_incremental_pages(&self) -> Option<usize> {
+        self.max_incremental_pages
+    }
+    /// Gets the maximum number of this template's pages that can be built at
+    /// once, if there is one.
+    #[cfg(engine)]
+    pub fn get_build_concurrency(&self) -> Option<usize> {
+        self.build_concurrency
+    }
     /// Checks if this template is a template to generate paths beneath it.
     #[cfg(engine)]
     pub fn uses_build_paths(&self) -> bool {
@@ -18,7 +18,7 @@ pub(crate) use utils::*;
 use super::fn_types::*;
 use super::TemplateFn;
 #[cfg(engine)]
-use crate::utils::ComputedDuration;
+use crate::utils::{ComputedDuration, CronSchedule};
 use sycamore::{prelude::create_scope, view::View, web::Html};
 
 /// A single template in an app. Each template is comprised of a Sycamore view,
@@ -104,11 +104,31 @@ pub struct TemplateInner<G: Html> {
     /// can use a different template.
     #[cfg(engine)]
     incremental_generation: bool,
+    /// A function that checks whether or not a path that matches this template
+    /// but wasn't built at build-time is acceptable for incremental
+    /// generation. If it rejects the path, a 404 will be returned, without
+    /// any state being generated.
+    #[cfg(engine)]
+    incremental_path_validator: Option<IncrementalPathValidatorFn>,
+    /// The maximum number of pages (across all locales) that can be
+    /// incrementally generated for this template. Once this is reached, any
+    /// requests for new pages will receive a 404.
+    #[cfg(engine)]
+    max_incremental_pages: Option<usize>,
+    /// The maximum number of this template's pages whose state will be
+    /// generated at once at build-time. This applies in addition to any
+    /// app-wide limit.
+    #[cfg(engine)]
+    build_concurrency: Option<usize>,
     /// A function that gets the initial state to use to prerender the template
     /// at build time. This will be passed the path of the template, and
     /// will be run for any sub-paths.
     #[cfg(engine)]
     get_build_state: Option<GetBuildStateFn>,
+    /// A function that decides whether or not requests for this template's
+    /// pages should be allowed, run before any state is generated for them.
+    #[cfg(engine)]
+    guard: Option<GuardFn>,
     /// A function that will run on every request to generate a state for that
     /// request. This allows server-side-rendering. This can be used with
     /// `get_build_state`, though custom amalgamation logic must be provided.
@@ -130,6 +150,17 @@ pub struct TemplateInner<G: Html> {
     /// that with `should_revalidate`).
     #[cfg(engine)]
     revalidate_after: Option<ComputedDuration>,
+    /// A cron schedule of wall-clock times at which to prerender the template
+    /// again. As with `revalidate_after`, the next request after each of
+    /// these times will lead to a revalidation. If both are provided,
+    /// whichever deadline comes first will be used.
+    #[cfg(engine)]
+    revalidate_at: Option<CronSchedule>,
+    /// Whether or not revalidations should be run in the background, with
+    /// the stale state being served to the request that triggered them,
+    /// rather than making that request wait for the new state.
+    #[cfg(engine)]
+    revalidate_in_background: bool,
     /// Custom logic to amalgamate potentially different states generated at
     /// build and request time. This is only necessary if your template uses
     /// both `build_state` and `request_state`. If not specified and both are
@@ -149,6 +180,10 @@ pub struct TemplateInner<G: Html> {
     /// is needed and it hasn't been explicitly allowed, an error will be
     /// returned from the build process.
     pub(crate) can_be_rescheduled: bool,
+    /// Whether or not this template has a guard. Unlike the guard itself, this
+    /// is available on the browser-side, which needs to know not to serve
+    /// guarded pages without asking the server.
+    is_guarded: bool,
 }
 impl<G: Html> std::fmt::Debug for TemplateInner<G> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -176,18 +211,31 @@ impl<G: Html> TemplateInner<G> {
             #[cfg(engine)]
             incremental_generation: false,
             #[cfg(engine)]
+            incremental_path_validator: None,
+            #[cfg(engine)]
+            max_incremental_pages: None,
+            #[cfg(engine)]
+            build_concurrency: None,
+            #[cfg(engine)]
             get_build_state: None,
             #[cfg(engine)]
+            guard: None,
+            #[cfg(engine)]
             get_request_state: None,
             #[cfg(engine)]
             should_revalidate: None,
             #[cfg(engine)]
             revalidate_after: None,
             #[cfg(engine)]
+            revalidate_at: None,
+            #[cfg(engine)]
+            revalidate_in_background: false,
+            #[cfg(engine)]
             amalgamate_states: None,
             // There is no mechanism to set this to `true`, except through the `Capsule` struct
             is_capsule: false,
             can_be_rescheduled: false,
+            is_guarded: false,
         }
     }
     /// Builds a full [`Template`] from this [`TemplateInner`], consuming it in
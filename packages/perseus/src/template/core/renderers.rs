@@ -12,6 +12,8 @@ use crate::state::TemplateState;
 use crate::state::{BuildPaths, StateGeneratorInfo, UnknownStateType};
 #[cfg(engine)]
 use crate::template::default_headers;
+#[cfg(engine)]
+use crate::template::GuardVerdict;
 use crate::template::TemplateInner;
 #[cfg(engine)]
 use crate::Request;
@@ -221,6 +223,39 @@ impl<G: Html> TemplateInner<G> {
             .into())
         }
     }
+    /// Checks, by the user's custom logic, if the given path may be
+    /// incrementally generated. Errors here can be caused by either the server
+    /// or the client, so the user must specify an [`ErrorBlame`].
+    #[cfg(engine)]
+    pub(crate) async fn validate_incremental_path(
+        &self,
+        info: StateGeneratorInfo<UnknownStateType>,
+        req: Request,
+    ) -> Result<bool, ServerError> {
+        if let Some(incremental_path_validator) = &self.incremental_path_validator {
+            incremental_path_validator.call(info, req).await
+        } else {
+            Err(BuildError::TemplateFeatureNotEnabled {
+                template_name: self.path.clone(),
+                feature_name: "incremental_path_validator".to_string(),
+            }
+            .into())
+        }
+    }
+    /// Runs the template's guard, if it has one, to decide whether or not the
+    /// given request should be allowed. Templates without guards allow
+    /// everything.
+    #[cfg(engine)]
+    pub(crate) async fn run_guard(
+        &self,
+        info: StateGeneratorInfo<UnknownStateType>,
+        req: Request,
+    ) -> Result<GuardVerdict, ServerError> {
+        match &self.guard {
+            Some(guard) => guard.call(info, req).await,
+            None => Ok(GuardVerdict::Allow),
+        }
+    }
     /// Gets the template's headers for the given state. These will be inserted
     /// into any successful HTTP responses for this template, and they have
     /// the power to override existing headers, including `Content-Type`.
@@ -10,6 +10,10 @@ use crate::state::{BuildPaths, MakeRx};
 #[cfg(engine)]
 use crate::state::{StateGeneratorInfo, TemplateState, UnknownStateType};
 #[cfg(engine)]
+use crate::utils::CronSchedule;
+#[cfg(engine)]
+use fmterr::fmt_err;
+#[cfg(engine)]
 use http::HeaderMap;
 #[cfg(engine)]
 use serde::{de::DeserializeOwned, Serialize};
@@ -112,6 +116,105 @@ impl<G: Html> TemplateInner<G> {
         self
     }
 
+    /// Sets a function that will be used to check whether or not a path that
+    /// matches this template, but that wasn't built at build-time, should be
+    /// incrementally generated. If this returns `false`, a 404 will be
+    /// returned, and no state will be generated (or stored). This is useful
+    /// for preventing crawlers or malicious clients from filling your mutable
+    /// store with junk pages.
+    ///
+    /// This only has an effect if incremental generation is enabled.
+    #[cfg(engine)]
+    pub fn incremental_path_validator_fn<B, V>(
+        mut self,
+        val: impl IncrementalPathValidatorUserFnType<B, V> + Clone + Send + Sync + 'static,
+    ) -> Self
+    where
+        B: Serialize + DeserializeOwned + Send + Sync + 'static,
+        V: Into<BlamedGeneratorResult<bool>>,
+    {
+        let template_name = self.get_path();
+        self.incremental_path_validator = Some(Box::new(
+            move |info: StateGeneratorInfo<UnknownStateType>, req| {
+                let val = val.clone();
+                let template_name = template_name.clone();
+                async move {
+                    let user_info = info.change_type::<B>();
+                    val.call(user_info, req)
+                        .await
+                        .into()
+                        .into_server_result("incremental_path_validator", template_name)
+                }
+            },
+        ));
+        self
+    }
+    /// Sets a function that will be used to check whether or not a path that
+    /// matches this template, but that wasn't built at build-time, should be
+    /// incrementally generated. If this returns `false`, a 404 will be
+    /// returned, and no state will be generated (or stored). This is useful
+    /// for preventing crawlers or malicious clients from filling your mutable
+    /// store with junk pages.
+    ///
+    /// This only has an effect if incremental generation is enabled.
+    #[cfg(any(client, doc))]
+    pub fn incremental_path_validator_fn(self, _val: impl Fn() + 'static) -> Self {
+        self
+    }
+    /// Sets the maximum number of pages that can be incrementally generated
+    /// for this template (counting each locale separately). Once this many
+    /// pages have been generated, requests for any new ones will receive a
+    /// 404, although pages that have already been generated will continue to
+    /// be served as usual.
+    ///
+    /// This limit is only enforced within a single server process: if you run
+    /// several instances that share a mutable store, they may together
+    /// generate slightly more pages than this if they do so at the same time.
+    ///
+    /// This only has an effect if incremental generation is enabled.
+    #[cfg(engine)]
+    pub fn max_incremental_pages(mut self, val: usize) -> Self {
+        self.max_incremental_pages = Some(val);
+        self
+    }
+    /// Sets the maximum number of pages that can be incrementally generated
+    /// for this template (counting each locale separately). Once this many
+    /// pages have been generated, requests for any new ones will receive a
+    /// 404, although pages that have already been generated will continue to
+    /// be served as usual.
+    ///
+    /// This limit is only enforced within a single server process: if you run
+    /// several instances that share a mutable store, they may together
+    /// generate slightly more pages than this if they do so at the same time.
+    ///
+    /// This only has an effect if incremental generation is enabled.
+    #[cfg(any(client, doc))]
+    pub fn max_incremental_pages(self, _val: usize) -> Self {
+        self
+    }
+
+    /// Sets the maximum number of this template's pages whose state will be
+    /// generated at once at build-time. This is useful if your build paths
+    /// function returns a large number of paths whose state generation calls
+    /// some API that can't handle many requests at once. This applies in
+    /// addition to any app-wide limit set with
+    /// `PerseusApp::build_concurrency()`.
+    #[cfg(engine)]
+    pub fn build_concurrency(mut self, val: usize) -> Self {
+        self.build_concurrency = Some(val);
+        self
+    }
+    /// Sets the maximum number of this template's pages whose state will be
+    /// generated at once at build-time. This is useful if your build paths
+    /// function returns a large number of paths whose state generation calls
+    /// some API that can't handle many requests at once. This applies in
+    /// addition to any app-wide limit set with
+    /// `PerseusApp::build_concurrency()`.
+    #[cfg(any(client, doc))]
+    pub fn build_concurrency(self, _val: usize) -> Self {
+        self
+    }
+
     /// Enables the *build state* strategy with the given function.
     #[cfg(engine)]
     pub fn build_state_fn<S, B, V>(
@@ -148,6 +251,57 @@ impl<G: Html> TemplateInner<G> {
         self
     }
 
+    /// Sets a function that will be run before any state is generated for
+    /// this template's pages, on both initial and subsequent loads. This is
+    /// given the request, and can decide to let it through, deny it with an
+    /// HTTP status code (which will render your error views), or redirect the
+    /// user elsewhere, making it ideal for authentication and authorization.
+    ///
+    /// Since guards depend on the request, pages using them will always be
+    /// fetched from the server on subsequent loads, even if they've been
+    /// loaded before, and apps using them can't be exported.
+    #[cfg(engine)]
+    pub fn guard_fn<B, V>(
+        mut self,
+        val: impl GuardUserFnType<B, V> + Clone + Send + Sync + 'static,
+    ) -> Self
+    where
+        B: Serialize + DeserializeOwned + Send + Sync + 'static,
+        V: Into<BlamedGeneratorResult<GuardVerdict>>,
+    {
+        let template_name = self.get_path();
+        self.guard = Some(Box::new(
+            move |info: StateGeneratorInfo<UnknownStateType>, req| {
+                let val = val.clone();
+                let template_name = template_name.clone();
+                async move {
+                    let user_info = info.change_type::<B>();
+                    val.call(user_info, req)
+                        .await
+                        .into()
+                        .into_server_result("guard", template_name)
+                }
+            },
+        ));
+        self.is_guarded = true;
+        self
+    }
+    /// Sets a function that will be run before any state is generated for
+    /// this template's pages, on both initial and subsequent loads. This is
+    /// given the request, and can decide to let it through, deny it with an
+    /// HTTP status code (which will render your error views), or redirect the
+    /// user elsewhere, making it ideal for authentication and authorization.
+    ///
+    /// Since guards depend on the request, pages using them will always be
+    /// fetched from the server on subsequent loads, even if they've been
+    /// loaded before, and apps using them can't be exported.
+    #[cfg(any(client, doc))]
+    pub fn guard_fn(mut self, _val: impl Fn() + 'static) -> Self {
+        // The browser needs to know about this so it always asks the server
+        self.is_guarded = true;
+        self
+    }
+
     /// Enables the *request state* strategy with the given function.
     #[cfg(engine)]
     pub fn request_state_fn<S, B, V>(
@@ -256,6 +410,93 @@ impl<G: Html> TemplateInner<G> {
         self
     }
 
+    /// Enables the *revalidation* strategy (time variant) with a schedule of
+    /// wall-clock times, given as a cron expression. For example, `0 * * *
+    /// *` will revalidate on the hour, and `0 6 * * *` will revalidate at
+    /// 06:00 every day (all times are in UTC). As with `.revalidate_after()`,
+    /// revalidation is lazy, so the next request after each of these times
+    /// will lead to a revalidation.
+    ///
+    /// This takes either a standard five-field cron expression, or a six- or
+    /// seven-field expression with seconds at the start (and, optionally,
+    /// years at the end). In five-field expressions, numeric days of the
+    /// week are standard, running from 0 (Sunday) to 6 (Saturday), with 7
+    /// also meaning Sunday; in the longer forms, they run from 1 (Sunday) to
+    /// 7 (Saturday). Either way, it's clearer to use names like `Mon-Fri`. If
+    /// this is
+    /// used together with `.revalidate_after()`, whichever deadline comes
+    /// first will be used.
+    #[cfg(engine)]
+    pub fn revalidate_at(mut self, cron_expr: &str) -> Self {
+        let schedule = match CronSchedule::parse(cron_expr) {
+            Ok(schedule) => schedule,
+            // As with intervals, this will show up when we try to build the app
+            Err(err) => panic!("invalid revalidation schedule: {}", fmt_err(&err)),
+        };
+        self.revalidate_at = Some(schedule);
+        self
+    }
+    /// Enables the *revalidation* strategy (time variant) with a schedule of
+    /// wall-clock times, given as a cron expression. For example, `0 * * *
+    /// *` will revalidate on the hour, and `0 6 * * *` will revalidate at
+    /// 06:00 every day (all times are in UTC). As with `.revalidate_after()`,
+    /// revalidation is lazy, so the next request after each of these times
+    /// will lead to a revalidation.
+    ///
+    /// This takes either a standard five-field cron expression, or a six- or
+    /// seven-field expression with seconds at the start (and, optionally,
+    /// years at the end). In five-field expressions, numeric days of the
+    /// week are standard, running from 0 (Sunday) to 6 (Saturday), with 7
+    /// also meaning Sunday; in the longer forms, they run from 1 (Sunday) to
+    /// 7 (Saturday). Either way, it's clearer to use names like `Mon-Fri`. If
+    /// this is
+    /// used together with `.revalidate_after()`, whichever deadline comes
+    /// first will be used.
+    #[cfg(any(client, doc))]
+    pub fn revalidate_at(self, _cron_expr: &str) -> Self {
+        self
+    }
+
+    /// Makes revalidations of this template's pages run in the background
+    /// (*stale-while-revalidate*). When a request finds that a page should
+    /// revalidate, it will be served the existing (stale) state immediately,
+    /// and the new state will be generated after the response has been
+    /// sent, ready for subsequent requests. Without this, the request that
+    /// triggers a revalidation must wait for the whole state generation
+    /// process.
+    ///
+    /// This has no effect unless this template revalidates, and requires the
+    /// server to be running background tasks (the default engine does this
+    /// automatically), otherwise revalidations will happen at request-time
+    /// as usual. Pages that are being generated for the first time through
+    /// incremental generation, or whose stale state the mutable store has
+    /// lost (e.g. after a restart with an in-memory store), will always be
+    /// generated at request-time.
+    #[cfg(engine)]
+    pub fn revalidate_in_background(mut self) -> Self {
+        self.revalidate_in_background = true;
+        self
+    }
+    /// Makes revalidations of this template's pages run in the background
+    /// (*stale-while-revalidate*). When a request finds that a page should
+    /// revalidate, it will be served the existing (stale) state immediately,
+    /// and the new state will be generated after the response has been
+    /// sent, ready for subsequent requests. Without this, the request that
+    /// triggers a revalidation must wait for the whole state generation
+    /// process.
+    ///
+    /// This has no effect unless this template revalidates, and requires the
+    /// server to be running background tasks (the default engine does this
+    /// automatically), otherwise revalidations will happen at request-time
+    /// as usual. Pages that are being generated for the first time through
+    /// incremental generation, or whose stale state the mutable store has
+    /// lost (e.g. after a restart with an in-memory store), will always be
+    /// generated at request-time.
+    #[cfg(any(client, doc))]
+    pub fn revalidate_in_background(self) -> Self {
+        self
+    }
+
     /// Enables state amalgamation with the given function. State amalgamation
     /// allows you to have one template generate state at both build time
     /// and request time. The function you provide here is responsible for
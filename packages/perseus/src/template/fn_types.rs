@@ -1,8 +1,9 @@
 use crate::{
     errors::*,
     make_async_trait,
+    router::is_redirect_status,
     state::{BuildPaths, MakeRx, StateGeneratorInfo, TemplateState, UnknownStateType},
-    utils::AsyncFnReturn,
+    utils::{get_path_prefix_server, AsyncFnReturn},
     Request,
 };
 use futures::Future;
@@ -68,12 +69,26 @@ impl<T> GeneratorResult<T> {
 /// either `T` directly, or `Result<T, BlamedError<E>>`: see [`BlamedError`] for
 /// further information. (Note that the `?` operator can automatically turn `E`
 /// into `BlamedError<E>`, setting the server as the one to blame.)
+///
+/// Request-time state generation functions can also return a
+/// [`RequestOutcome`], which will be converted into one of the extra variants
+/// here.
 #[derive(Debug)]
 pub enum BlamedGeneratorResult<T> {
     /// Equivalent to `Result::Ok`.
     Ok(T),
     /// Equivalent to `Result::Err`.
     Err(GenericBlamedError),
+    /// The user should be redirected to the given path within the app, with
+    /// the given HTTP status code.
+    Redirect {
+        /// The path to redirect to.
+        dest: String,
+        /// The status code to redirect with.
+        status: u16,
+    },
+    /// The request should be answered with the given HTTP status code.
+    Status(u16),
 }
 impl<T> BlamedGeneratorResult<T> {
     /// Converts this `enum` into a `Result` amenable to typical usage within
@@ -91,10 +106,100 @@ impl<T> BlamedGeneratorResult<T> {
                 blame: err.blame,
                 source: err.error,
             }),
+            Self::Redirect { status, .. } if !is_redirect_status(status) => {
+                Err(ServeError::InvalidRedirectStatus {
+                    fn_name: fn_name.to_string(),
+                    template_name,
+                    status,
+                }
+                .into())
+            }
+            Self::Redirect { dest, status } => Err(ServeError::Redirect {
+                dest: format!("{}{}", get_path_prefix_server(), dest),
+                status,
+            }
+            .into()),
+            Self::Status(status) => Err(ServeError::StatusResponse {
+                fn_name: fn_name.to_string(),
+                template_name,
+                // Anything that isn't an error code would be meaningless here
+                status: if (400..600).contains(&status) {
+                    status
+                } else {
+                    500
+                },
+            }
+            .into()),
+        }
+    }
+}
+
+/// The outcome of a request-time state generation function (i.e. one for
+/// request state or state amalgamation), which can be returned instead of the
+/// state itself if the request shouldn't be answered with a page. This can
+/// also be wrapped in a `Result<RequestOutcome<S>, BlamedError<E>>`.
+///
+/// Note that returning anything other than `RequestOutcome::State` from a
+/// function run at build-time will fail the build.
+#[derive(Debug, Clone)]
+pub enum RequestOutcome<S> {
+    /// The page should be rendered with the given state.
+    State(S),
+    /// The user should be sent to the given path within your app (e.g.
+    /// `/products`, or `/en-US/products` in an app using i18n) with the given
+    /// HTTP status code, which must be one of `301`, `302`, `303`, `307`, or
+    /// `308` (anything else will lead to a *500 Internal Server Error*). Use
+    /// `308` for things that have moved permanently, and `307` otherwise. The
+    /// path prefix will be prepended automatically.
+    Redirect {
+        /// The path to redirect to.
+        dest: String,
+        /// The status code to redirect with.
+        status: u16,
+    },
+    /// The request should be answered with the given HTTP status code (e.g.
+    /// *410 Gone* for a product that's been deleted), which should be a 4xx
+    /// or 5xx code (anything else will be treated as a *500 Internal Server
+    /// Error*). The user will be shown your error views for that code.
+    Status(u16),
+}
+impl<S> From<RequestOutcome<S>> for BlamedGeneratorResult<S> {
+    fn from(val: RequestOutcome<S>) -> Self {
+        match val {
+            RequestOutcome::State(val) => Self::Ok(val),
+            RequestOutcome::Redirect { dest, status } => Self::Redirect { dest, status },
+            RequestOutcome::Status(status) => Self::Status(status),
+        }
+    }
+}
+impl<S, E: Into<Box<dyn std::error::Error + Send + Sync + 'static>> + Send + Sync>
+    From<Result<RequestOutcome<S>, BlamedError<E>>> for BlamedGeneratorResult<S>
+{
+    fn from(val: Result<RequestOutcome<S>, BlamedError<E>>) -> Self {
+        match val {
+            Ok(val) => val.into(),
+            Err(err) => Self::Err(err.into_boxed()),
         }
     }
 }
 
+/// The outcome of a template's guard function, which decides whether or not a
+/// request for one of its pages should go ahead. See `Template::guard_fn` for
+/// further details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardVerdict {
+    /// The request should be served as usual.
+    Allow,
+    /// The request should be denied with the given HTTP status code, which
+    /// should be a 4xx or 5xx code (anything else will be treated as a *403
+    /// Forbidden*). The user will be shown your error views for that code.
+    Deny(u16),
+    /// The user should be sent to the given path within your app (e.g.
+    /// `/login`, or `/en-US/login` in an app using i18n), which will have
+    /// the path prefix prepended automatically.
+    Redirect(String),
+}
+
 // We manually implement everything we need here (and only what we need). A
 // neater approach would be a `MaybeFallible` trait, but that needs an
 // implementation for both `T` and `Result<T, E>`, which overlap. With
@@ -202,6 +307,22 @@ impl<E: Into<Box<dyn std::error::Error + Send + Sync + 'static>> + Send + Sync>
         }
     }
 }
+// Guards (blamed)
+impl From<GuardVerdict> for BlamedGeneratorResult<GuardVerdict> {
+    fn from(val: GuardVerdict) -> Self {
+        Self::Ok(val)
+    }
+}
+impl<E: Into<Box<dyn std::error::Error + Send + Sync + 'static>> + Send + Sync>
+    From<Result<GuardVerdict, BlamedError<E>>> for BlamedGeneratorResult<GuardVerdict>
+{
+    fn from(val: Result<GuardVerdict, BlamedError<E>>) -> Self {
+        match val {
+            Ok(val) => Self::Ok(val),
+            Err(err) => Self::Err(err.into_boxed()),
+        }
+    }
+}
 
 // A series of asynchronous closure traits that prevent the user from having to
 // pin their functions
@@ -228,6 +349,18 @@ make_async_trait!(
     info: StateGeneratorInfo<UnknownStateType>,
     req: Request
 );
+make_async_trait!(
+    pub(super) IncrementalPathValidatorFnType,
+    Result<bool, ServerError>,
+    info: StateGeneratorInfo<UnknownStateType>,
+    req: Request
+);
+make_async_trait!(
+    pub(super) GuardFnType,
+    Result<GuardVerdict, ServerError>,
+    info: StateGeneratorInfo<UnknownStateType>,
+    req: Request
+);
 make_async_trait!(
     pub(super) AmalgamateStatesFnType,
     Result<TemplateState, ServerError>,
@@ -261,6 +394,18 @@ make_async_trait!(
     info: StateGeneratorInfo<B>,
     req: Request
 );
+make_async_trait!(
+    pub IncrementalPathValidatorUserFnType< B: Serialize + DeserializeOwned + Send + Sync, V: Into< BlamedGeneratorResult<bool> >  >,
+    V,
+    info: StateGeneratorInfo<B>,
+    req: Request
+);
+make_async_trait!(
+    pub GuardUserFnType< B: Serialize + DeserializeOwned + Send + Sync, V: Into< BlamedGeneratorResult<GuardVerdict> >  >,
+    V,
+    info: StateGeneratorInfo<B>,
+    req: Request
+);
 make_async_trait!(
     pub AmalgamateStatesUserFnType< S: Serialize + DeserializeOwned + MakeRx, B: Serialize + DeserializeOwned + Send + Sync, V: Into< BlamedGeneratorResult<S> > >,
     V,
@@ -290,5 +435,9 @@ pub(crate) type GetBuildStateFn = Box<dyn GetBuildStateFnType + Send + Sync>;
 pub(crate) type GetRequestStateFn = Box<dyn GetRequestStateFnType + Send + Sync>;
 /// The type of functions that check if a template should revalidate.
 pub(crate) type ShouldRevalidateFn = Box<dyn ShouldRevalidateFnType + Send + Sync>;
+/// The type of functions that check if a path may be incrementally generated.
+pub(crate) type IncrementalPathValidatorFn = Box<dyn IncrementalPathValidatorFnType + Send + Sync>;
+/// The type of functions that guard a template's pages.
+pub(crate) type GuardFn = Box<dyn GuardFnType + Send + Sync>;
 /// The type of functions that amalgamate build and request states.
 pub(crate) type AmalgamateStatesFn = Box<dyn AmalgamateStatesFnType + Send + Sync>;
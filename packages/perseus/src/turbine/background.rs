@@ -0,0 +1,152 @@
+use super::Turbine;
+use crate::{
+    errors::ServerError, i18n::TranslationsManager, path::PurePath, state::TemplateState,
+    stores::MutableStore,
+};
+use fmterr::fmt_err;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    StreamExt,
+};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+/// A revalidation that's been deferred until after the response to the request
+/// that triggered it has been sent.
+#[derive(Debug)]
+pub(super) struct RevalidationJob {
+    /// The locale and encoded path, which uniquely identifies this page/widget
+    /// in the stores.
+    pub(super) key: String,
+    pub(super) entity_name: String,
+    pub(super) path: PurePath,
+    pub(super) locale: String,
+    pub(super) extra: TemplateState,
+    pub(super) global_state: TemplateState,
+    pub(super) was_incremental: bool,
+}
+
+/// The queue of revalidations waiting to be run in the background.
+#[derive(Debug)]
+pub(super) struct BackgroundQueue {
+    sender: UnboundedSender<RevalidationJob>,
+    /// This will be taken by whatever starts processing the queue.
+    receiver: Mutex<Option<UnboundedReceiver<RevalidationJob>>>,
+    /// Whether or not anything is actually processing the queue. If not,
+    /// revalidations will be run at request-time as usual.
+    running: AtomicBool,
+    /// The keys of every page/widget that's either in the queue or being
+    /// revalidated right now, so we don't queue the same work twice while
+    /// stale state is being served.
+    pending: Mutex<HashSet<String>>,
+}
+impl Default for BackgroundQueue {
+    fn default() -> Self {
+        let (sender, receiver) = unbounded();
+        Self {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            running: AtomicBool::new(false),
+            pending: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Runs any tasks the server has deferred to the background, such as the
+    /// revalidation of pages whose templates use
+    /// `.revalidate_in_background()`. This will not return until the turbine
+    /// is dropped, and it should be run concurrently with your server. The
+    /// default engine does this for you automatically, so you'll only need
+    /// this if you're running a custom engine.
+    ///
+    /// If this is never called, everything that would have been run in the
+    /// background will be run at request-time instead. If this is called
+    /// more than once, all but the first call will return immediately.
+    pub async fn run_background_tasks(&self) {
+        let receiver = self
+            .background
+            .receiver
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+        let receiver = match receiver {
+            Some(receiver) => receiver,
+            None => return,
+        };
+        self.background.running.store(true, Ordering::SeqCst);
+
+        receiver
+            .for_each_concurrent(None, |job| async move {
+                let key = job.key.clone();
+                if let Err(err) = self.run_revalidation_job(job).await {
+                    // There's no request to report this to, and the stale state will be
+                    // kept, so the next request will just try again
+                    tracing::error!(
+                        "background revalidation of '{}' failed: {}",
+                        key,
+                        fmt_err(&err)
+                    );
+                }
+                self.background
+                    .pending
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .remove(&key);
+            })
+            .await;
+    }
+
+    /// Queues the given revalidation to be run in the background, if anything
+    /// is processing background tasks. If this returns `false`, the caller
+    /// should perform the revalidation itself.
+    pub(super) fn queue_background_revalidation(&self, job: RevalidationJob) -> bool {
+        if !self.background.running.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let mut pending = self
+            .background
+            .pending
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        // If this is already being revalidated, there's nothing more to do
+        if pending.contains(&job.key) {
+            return true;
+        }
+        let key = job.key.clone();
+        match self.background.sender.unbounded_send(job) {
+            Ok(_) => {
+                pending.insert(key);
+                true
+            }
+            // The queue is no longer being processed
+            Err(_) => false,
+        }
+    }
+
+    /// Runs the given revalidation.
+    async fn run_revalidation_job(&self, job: RevalidationJob) -> Result<(), ServerError> {
+        // This was taken from the entities map, so it will definitely still be there
+        let entity = match self.entities.get(&job.entity_name) {
+            Some(entity) => entity,
+            None => return Ok(()),
+        };
+        self.build_path_or_widget_once(
+            job.path,
+            entity,
+            &job.extra,
+            &job.locale,
+            job.global_state,
+            job.was_incremental,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
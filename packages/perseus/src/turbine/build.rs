@@ -1,4 +1,4 @@
-use super::Turbine;
+use super::{health::HEALTH_MARKER, Turbine};
 use crate::{
     errors::*,
     i18n::{TranslationsManager, Translator},
@@ -8,17 +8,18 @@ use crate::{
     reactor::{RenderMode, RenderStatus},
     router::{match_route, FullRouteVerdict},
     server::get_path_slice,
-    state::{BuildPaths, StateGeneratorInfo, TemplateState},
+    state::{BuildPaths, CacheTags, StateGeneratorInfo, TemplateState},
     stores::MutableStore,
     template::Entity,
     utils::{minify, ssr_fallible},
 };
 use futures::{
     future::{try_join_all, BoxFuture},
-    FutureExt,
+    stream, FutureExt, StreamExt, TryStreamExt,
 };
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 use sycamore::web::SsrNode;
+use tokio::sync::Semaphore;
 
 impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
     /// Builds your whole app for being run on a server. Do not use this
@@ -61,13 +62,22 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         self.global_state = self.build_global_state(exporting).await?;
 
         let mut render_cfg = HashMap::new();
+        // If the user has limited how many pages/widgets can be built at once, this
+        // will hold that many permits
+        let limiter = self
+            .build_concurrency
+            .map(|limit| Semaphore::new(limit.max(1)));
 
         // Now build every capsule's state in parallel (capsules are never rendered
         // outside a page)
         let mut capsule_futs = Vec::new();
         for capsule in self.entities.values() {
             if capsule.is_capsule {
-                capsule_futs.push(self.build_template_or_capsule(capsule, exporting));
+                capsule_futs.push(self.build_template_or_capsule(
+                    capsule,
+                    exporting,
+                    limiter.as_ref(),
+                ));
             }
         }
         let capsule_render_cfg_frags = try_join_all(capsule_futs).await?;
@@ -86,7 +96,11 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         let mut template_futs = Vec::new();
         for template in self.entities.values() {
             if !template.is_capsule {
-                template_futs.push(self.build_template_or_capsule(template, exporting));
+                template_futs.push(self.build_template_or_capsule(
+                    template,
+                    exporting,
+                    limiter.as_ref(),
+                ));
             }
         }
         let template_render_cfg_frags = try_join_all(template_futs).await?;
@@ -95,7 +109,11 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             render_cfg.extend(fragment.into_iter());
         }
 
-        // Now write the render config to the immutable store
+        // Leave a marker for readiness probes to check the store with, since the
+        // render config itself could be quite large
+        self.immutable_store.write(HEALTH_MARKER, "OK").await?;
+        // Now write the render config to the immutable store (this should be the
+        // last thing we write)
         self.immutable_store
             .write(
                 "render_conf.json",
@@ -113,6 +131,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         )
         .await?;
         self.html_shell = Some(html_shell);
+        self.translations_loaded = self.check_translations().await;
 
         Ok(())
     }
@@ -163,6 +182,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         &self,
         entity: &Entity<SsrNode>,
         exporting: bool,
+        limiter: Option<&Semaphore>,
     ) -> Result<HashMap<String, String>, ServerError> {
         // If we're exporting, ensure that all the capsule's strategies are export-safe
         // (not requiring a server)
@@ -170,6 +190,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             && (entity.revalidates() ||
                 entity.uses_incremental() ||
                 entity.uses_request_state() ||
+                entity.uses_guard() ||
                 // We check amalgamation as well because it involves request state, even if that wasn't provided
                 entity.can_amalgamate_states())
         {
@@ -239,19 +260,38 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             for path in paths.into_iter() {
                 for locale in self.locales.get_all() {
                     let path = PurePath(path.clone());
-                    path_futs.push(self.build_path_or_widget_for_locale(
-                        path,
-                        entity,
-                        &extra,
-                        locale,
-                        self.global_state.clone(),
-                        exporting,
-                        false,
-                    ));
+                    let extra = &extra;
+                    path_futs.push(async move {
+                        // We only limit the top-level builds, since any widgets that have
+                        // to be built for this page will be built within it (limiting those
+                        // too could deadlock)
+                        let _permit = match limiter {
+                            Some(limiter) => limiter.acquire().await.ok(),
+                            None => None,
+                        };
+                        self.build_path_or_widget_for_locale(
+                            path,
+                            entity,
+                            extra,
+                            locale,
+                            self.global_state.clone(),
+                            exporting,
+                            false,
+                        )
+                        .await
+                    });
                 }
             }
             // Extend the render configuration with any incrementally generated widgets
-            let render_cfg_exts = try_join_all(path_futs).await?;
+            let render_cfg_exts: Vec<_> = match entity.get_build_concurrency() {
+                Some(limit) => {
+                    stream::iter(path_futs)
+                        .buffer_unordered(limit.max(1))
+                        .try_collect()
+                        .await?
+                }
+                None => try_join_all(path_futs).await?,
+            };
             for ext in render_cfg_exts {
                 render_cfg_frag.extend(ext.into_iter());
             }
@@ -289,16 +329,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             .get_translator_for_locale(locale.to_string())
             .await?;
 
-        let full_path_without_locale = PathWithoutLocale(match entity.uses_build_paths() {
-            // Note the stripping of trailing `/`s here (otherwise index build paths fail)
-            true => {
-                let full = format!("{}/{}", &entity.get_path(), path.0);
-                let full = full.strip_suffix('/').unwrap_or(&full);
-                full.strip_prefix('/').unwrap_or(full).to_string()
-            }
-            // We don't want to concatenate the name twice if we don't have to
-            false => entity.get_path(),
-        });
+        let full_path_without_locale = get_full_path_without_locale(&path, entity);
         // Create the encoded path, which always includes the locale (even if it's
         // `xx-XX` in a non-i18n app)
         //
@@ -317,11 +348,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
 
         // First, if this page revalidates, write a timestamp about when it was built to
         // the mutable store (this will be updated to keep track)
-        if entity.revalidates_with_time() {
-            let datetime_to_revalidate = entity
-                .get_revalidate_interval()
-                .unwrap()
-                .compute_timestamp();
+        if let Some(datetime_to_revalidate) = entity.compute_revalidation_timestamp() {
             // Note that different locales do have different revalidation schedules
             self.mutable_store
                 .write(
@@ -335,6 +362,14 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             // We don't bother writing the state of basic entities
             TemplateState::empty()
         } else if entity.uses_build_state() {
+            let in_mutable = force_mutable || entity.revalidates();
+            // If this was invalidated through a cache tag, we're dealing with that now (we
+            // do this first so we don't lose an invalidation that comes in while we're
+            // generating state)
+            if in_mutable {
+                self.set_invalidated(&full_path_encoded, false).await?;
+            }
+            let cache_tags = CacheTags::default();
             let build_state = entity
                 .get_build_state(StateGeneratorInfo {
                     // IMPORTANT: It is very easy to break Perseus here; always make sure this is
@@ -343,14 +378,28 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                     path: (*path).clone(),
                     locale: translator.get_locale(),
                     extra: extra.clone(),
+                    cache_tags: cache_tags.clone(),
                 })
-                .await?;
+                .await;
+            let build_state = match build_state {
+                Ok(build_state) => build_state,
+                Err(err) => {
+                    // We still need to revalidate next time
+                    if in_mutable {
+                        self.set_invalidated(&full_path_encoded, true).await?;
+                    }
+                    return Err(err);
+                }
+            };
             // Write the state to the appropriate store (mutable if the entity revalidates)
             let state_str = build_state.state.to_string();
-            if force_mutable || entity.revalidates() {
+            if in_mutable {
                 self.mutable_store
                     .write(&format!("static/{}.json", full_path_encoded), &state_str)
                     .await?;
+                // Only things in the mutable store can be invalidated
+                self.record_cache_tags(&full_path_encoded, cache_tags.take())
+                    .await?;
             } else {
                 self.immutable_store
                     .write(&format!("static/{}.json", full_path_encoded), &state_str)
@@ -650,3 +699,21 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         }.boxed()
     }
 }
+
+/// Gets the full path (without the locale) of the given path within the given
+/// page/widget, as it would be requested.
+pub(super) fn get_full_path_without_locale(
+    path: &PurePath,
+    entity: &Entity<SsrNode>,
+) -> PathWithoutLocale {
+    PathWithoutLocale(match entity.uses_build_paths() {
+        // Note the stripping of trailing `/`s here (otherwise index build paths fail)
+        true => {
+            let full = format!("{}/{}", &entity.get_path(), path.0);
+            let full = full.strip_suffix('/').unwrap_or(&full);
+            full.strip_prefix('/').unwrap_or(full).to_string()
+        }
+        // We don't want to concatenate the name twice if we don't have to
+        false => entity.get_path(),
+    })
+}
@@ -1,6 +1,7 @@
 use super::Turbine;
 use crate::error_views::ServerErrorData;
 use crate::i18n::TranslationsManager;
+use crate::server::HtmlShell;
 use crate::stores::MutableStore;
 use crate::translator::Translator;
 
@@ -29,6 +30,20 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         data: ServerErrorData,
         translator: Option<&Translator>,
     ) -> String {
+        let html_shell = self.html_shell.as_ref().unwrap().clone();
+        self.error_page_shell(html_shell, data, translator)
+            .to_string()
+    }
+    /// Interpolates an error page into the given HTML shell. This is the same
+    /// as `.build_error_page()`, but it allows using a shell that has already
+    /// been modified (e.g. for streaming initial loads, where part of the shell
+    /// has already been sent).
+    pub(crate) fn error_page_shell(
+        &self,
+        html_shell: HtmlShell,
+        data: ServerErrorData,
+        translator: Option<&Translator>,
+    ) -> HtmlShell {
         let (translator, locale) = if let Some(translator) = translator {
             (Some(translator), Some(translator.get_locale()))
         } else {
@@ -37,12 +52,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
 
         let (head, body) = self.error_views.render_to_string(data.clone(), translator);
 
-        self.html_shell
-            .as_ref()
-            .unwrap()
-            .clone()
-            // This will inject the translations string if it's available
-            .error_page(&data, &body, &head, locale)
-            .to_string()
+        // This will inject the translations string if it's available
+        html_shell.error_page(&data, &body, &head, locale)
     }
 }
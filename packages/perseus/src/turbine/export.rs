@@ -5,6 +5,7 @@ use crate::{
     internal::{PageData, PageDataPartial},
     path::PathMaybeWithLocale,
     plugins::PluginAction,
+    router::redirect_stub,
     state::TemplateState,
     stores::MutableStore,
     utils::get_path_prefix_server,
@@ -107,11 +108,30 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             try_join_all(initial_const_futs),
         )
         .await?;
+        // Redirects take priority over pages, so these are written last (they go
+        // in the same files as the pages at their sources, which they'll overwrite)
+        self.export_redirects().await?;
 
         // Copying in bundles from the filesystem is done externally to this function
 
         Ok(())
     }
+    /// Writes a page that redirects the user for each of the app's redirects
+    /// that can be represented statically (i.e. those without parameters).
+    /// Exported apps can't send real redirects, so these just redirect as soon
+    /// as they're loaded.
+    async fn export_redirects(&self) -> Result<(), ServerError> {
+        let path_prefix = get_path_prefix_server();
+        let mut redirect_futs = Vec::new();
+        for (from, to) in self.route_rules.static_redirects() {
+            let stub_path = format!("exported/{}.html", initial_load_path(from));
+            let stub = redirect_stub(&format!("{}{}", path_prefix, to));
+            redirect_futs.push(async move { self.immutable_store.write(&stub_path, &stub).await });
+        }
+        try_join_all(redirect_futs).await?;
+
+        Ok(())
+    }
     /// Creates a JS file to hold the render configuration and the translations,
     /// to avoid putting too much stuff in the initial HTML file (this
     /// enables generally faster page loads, especially for large sites).
@@ -140,16 +160,7 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         // But we don't create a flattened system with exporting, everything is properly
         // created in a directory structure
         let path_encoded = urlencoding::encode(path).to_string();
-        // All initial load pages should be written into their own folders, which
-        // prevents a situation of a template root page outside the directory for the
-        // rest of that template's pages (see #73). The `.html` file extension is
-        // added when this variable is used (for contrast to the `.json`s)
-        let initial_load_path = if path.ends_with("index") {
-            // However, if it's already an index page, we don't want `index/index.html`
-            path.to_string()
-        } else {
-            format!("{}/index", &path)
-        };
+        let initial_load_path = initial_load_path(path);
 
         // Get the template itself
         let template = self.entities.get(template_path);
@@ -463,3 +474,68 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         Ok(())
     }
 }
+
+/// Gets the location in the export directory (without the `exported/` prefix or
+/// the `.html` extension) of the initial load page for the given path.
+fn initial_load_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    // All initial load pages should be written into their own folders, which
+    // prevents a situation of a template root page outside the directory for the
+    // rest of that template's pages (see #73)
+    if path.is_empty() {
+        "index".to_string()
+    } else if path.ends_with("index") {
+        // However, if it's already an index page, we don't want `index/index.html`
+        path.to_string()
+    } else {
+        format!("{}/index", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+    use crate::{stores::MemoryMutableStore, template::Template};
+    use futures::executor::block_on;
+
+    fn test_template(path: &str) -> Template<sycamore::web::SsrNode> {
+        Template::build(path)
+            .view(|cx| sycamore::view! { cx, p { "page" } })
+            .build()
+    }
+
+    #[test]
+    fn redirects_should_replace_exported_pages() {
+        let templates = vec![
+            test_template("index"),
+            test_template("about"),
+            test_template("docs/index"),
+            test_template("contact"),
+        ];
+        let mut turbine = test_turbine(templates, MemoryMutableStore::new());
+        for from in ["/", "/about", "/docs/index"] {
+            turbine.route_rules.add_redirect(from, "/contact", 308);
+        }
+        block_on(async {
+            turbine.build().await.unwrap();
+            turbine.export_internal().await.unwrap();
+
+            let stub = redirect_stub("/contact");
+            for file in ["index", "about/index", "docs/index"] {
+                let exported = turbine
+                    .immutable_store
+                    .read(&format!("exported/{}.html", file))
+                    .await
+                    .unwrap();
+                assert_eq!(exported, stub, "{} should have been replaced", file);
+            }
+            let contact = turbine
+                .immutable_store
+                .read("exported/contact/index.html")
+                .await
+                .unwrap();
+            assert_ne!(contact, stub);
+        });
+    }
+}
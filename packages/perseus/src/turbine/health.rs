@@ -0,0 +1,98 @@
+use super::Turbine;
+use crate::{i18n::TranslationsManager, stores::MutableStore};
+use serde::Serialize;
+
+/// The name of a tiny asset in the immutable store written by every build,
+/// which readiness probes read to check that the store can be reached.
+pub(super) const HEALTH_MARKER: &str = "static/health.txt";
+
+/// The health of a Perseus server, as reported by `Turbine::health()`. This is
+/// what readiness probes will receive as JSON.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// Whether or not the server is ready to serve pages. This will only be
+    /// `true` if everything else here is.
+    pub ready: bool,
+    /// Whether or not the render configuration and HTML shell have been
+    /// loaded (by `.populate_after_build()` or `.build()`).
+    pub populated: bool,
+    /// Whether or not the immutable store could be read from.
+    pub immutable_store_reachable: bool,
+    /// Whether or not the mutable store could be read from.
+    pub mutable_store_reachable: bool,
+    /// Whether or not the translations for every locale the app supports
+    /// could be loaded when the turbine was populated (they aren't checked
+    /// again). This is always `true` for apps not using i18n.
+    pub translations_loaded: bool,
+}
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Checks the health of this turbine, including whether or not it's been
+    /// populated with the artifacts of a build, and whether or not its
+    /// stores can actually be accessed. This is cheap enough to be called on
+    /// every readiness probe.
+    pub async fn health(&self) -> HealthStatus {
+        // The HTML shell is always loaded after the render configuration
+        let populated = self.html_shell.is_some();
+        // This will always exist after a build
+        let immutable_store_reachable = self.immutable_store.read(HEALTH_MARKER).await.is_ok();
+        // The mutable store might well be empty, so we just need to know it didn't fail
+        let mutable_store_reachable = self.mutable_store.exists("health").await.is_ok();
+        let translations_loaded = !self.locales.using_i18n || self.translations_loaded;
+
+        HealthStatus {
+            ready: populated
+                && immutable_store_reachable
+                && mutable_store_reachable
+                && translations_loaded,
+            populated,
+            immutable_store_reachable,
+            mutable_store_reachable,
+            translations_loaded,
+        }
+    }
+    /// Checks whether or not the translations for every locale the app
+    /// supports can be loaded, which is done once when the turbine is
+    /// populated, rather than on every readiness probe.
+    pub(super) async fn check_translations(&self) -> bool {
+        for locale in self.locales.get_all() {
+            if self
+                .translations_manager
+                .get_translations_str_for_locale(locale.to_string())
+                .await
+                .is_err()
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use crate::{stores::MemoryMutableStore, template::Template};
+    use futures::executor::block_on;
+
+    #[test]
+    fn should_be_ready_once_built() {
+        let template = Template::build("index")
+            .view(|cx| sycamore::view! { cx, p { "page" } })
+            .build();
+        let mut turbine = test_turbine(vec![template], MemoryMutableStore::new());
+        block_on(async {
+            let health = turbine.health().await;
+            assert!(!health.ready);
+            assert!(!health.populated);
+            assert!(!health.immutable_store_reachable);
+
+            turbine.build().await.unwrap();
+            let health = turbine.health().await;
+            assert!(health.ready);
+            assert!(health.immutable_store_reachable);
+            assert!(health.translations_loaded);
+        });
+    }
+}
@@ -0,0 +1,203 @@
+use super::Turbine;
+use crate::{
+    errors::*,
+    i18n::TranslationsManager,
+    state::{StateGeneratorInfo, UnknownStateType},
+    stores::MutableStore,
+    template::Entity,
+    Request,
+};
+use sycamore::web::SsrNode;
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Checks whether or not the given page/widget (as a locale and encoded
+    /// path), which hasn't been generated yet, may be incrementally generated,
+    /// according to its entity's path validator and limit on incremental
+    /// pages. If it may be, this will reserve a place for it in that limit,
+    /// which should be released if generation fails. If it may not be, this
+    /// will return a *404 Not Found* error.
+    pub(super) async fn reserve_incremental_page(
+        &self,
+        entity: &Entity<SsrNode>,
+        path_encoded: &str,
+        build_info: StateGeneratorInfo<UnknownStateType>,
+        req: Request,
+    ) -> Result<(), ServerError> {
+        let not_found = || {
+            ServerError::from(ServeError::PageNotFound {
+                path: build_info.path.to_string(),
+            })
+        };
+
+        if entity.uses_incremental_path_validator()
+            && !entity
+                .validate_incremental_path(build_info.clone(), req)
+                .await?
+        {
+            return Err(not_found());
+        }
+
+        if let Some(max_pages) = entity.get_max_incremental_pages() {
+            // Many new pages could be generated at once (this lock only covers this
+            // process, which is why the limit is documented as per-process)
+            let _guard = self.incremental_index_lock.lock().await;
+            let mut paths = self.read_incremental_index(entity).await?;
+            if !paths.iter().any(|path| path == path_encoded) {
+                if paths.len() >= max_pages {
+                    return Err(not_found());
+                }
+                paths.push(path_encoded.to_string());
+                self.write_incremental_index(entity, &paths).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Releases a place reserved with `.reserve_incremental_page()`, because
+    /// the page/widget couldn't be generated.
+    pub(super) async fn release_incremental_page(
+        &self,
+        entity: &Entity<SsrNode>,
+        path_encoded: &str,
+    ) -> Result<(), ServerError> {
+        if entity.get_max_incremental_pages().is_none() {
+            return Ok(());
+        }
+
+        let _guard = self.incremental_index_lock.lock().await;
+        let mut paths = self.read_incremental_index(entity).await?;
+        paths.retain(|path| path != path_encoded);
+        self.write_incremental_index(entity, &paths).await
+    }
+
+    /// Reads the list of pages/widgets (as locales and encoded paths) that have
+    /// been incrementally generated for the given entity.
+    async fn read_incremental_index(
+        &self,
+        entity: &Entity<SsrNode>,
+    ) -> Result<Vec<String>, ServerError> {
+        match self.mutable_store.read(&incremental_index(entity)).await {
+            Ok(paths_str) => serde_json::from_str(&paths_str).map_err(|err| {
+                ServerError::InvalidIncrementalIndex {
+                    template_name: entity.get_path(),
+                    source: err,
+                }
+            }),
+            Err(StoreError::NotFound { .. }) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+    /// Writes the list of pages/widgets that have been incrementally generated
+    /// for the given entity.
+    async fn write_incremental_index(
+        &self,
+        entity: &Entity<SsrNode>,
+        paths: &[String],
+    ) -> Result<(), ServerError> {
+        let paths_str = serde_json::to_string(paths).unwrap();
+        self.mutable_store
+            .write(&incremental_index(entity), &paths_str)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Gets the name of the asset in the mutable store that lists everything that
+/// has been incrementally generated for the given entity.
+fn incremental_index(entity: &Entity<SsrNode>) -> String {
+    format!(
+        "incremental/{}.json",
+        urlencoding::encode(&entity.get_path())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+    use crate::{
+        path::PathWithoutLocale, state::BuildPaths, stores::MemoryMutableStore, template::Template,
+    };
+    use futures::executor::block_on;
+
+    async fn get_build_state(
+        info: StateGeneratorInfo<()>,
+    ) -> Result<TestState, BlamedError<String>> {
+        if info.path == "broken" {
+            return Err(BlamedError {
+                error: "broken page".to_string(),
+                blame: ErrorBlame::Server(None),
+            });
+        }
+        Ok(TestState { path: info.path })
+    }
+    async fn get_build_paths() -> BuildPaths {
+        BuildPaths {
+            paths: Vec::new(),
+            extra: ().into(),
+        }
+    }
+    async fn validate_path(info: StateGeneratorInfo<()>, _req: Request) -> bool {
+        // Unlike state generation, this is given the full path
+        info.path != "post/invalid"
+    }
+
+    /// Gets the HTTP status code a request with the given result would get.
+    fn status<T>(res: Result<T, ServerError>) -> u16 {
+        match res {
+            Ok(_) => 200,
+            Err(err) => err_to_status_code(&err),
+        }
+    }
+
+    #[test]
+    fn incremental_pages_should_be_limited() {
+        let store = MemoryMutableStore::new();
+        let template = Template::build("post")
+            .view_with_unreactive_state(|cx, state: TestState| {
+                sycamore::view! { cx, p { (state.path) } }
+            })
+            .build_state_fn(get_build_state)
+            .build_paths_fn(get_build_paths)
+            .incremental_generation()
+            .incremental_path_validator_fn(validate_path)
+            .max_incremental_pages(1)
+            .build();
+        let mut turbine = test_turbine(vec![template], store.clone());
+        block_on(async {
+            turbine.build().await.unwrap();
+            let get = |path: &str| {
+                turbine.get_state_for_path(
+                    PathWithoutLocale(format!("post/{}", path)),
+                    "xx-XX".to_string(),
+                    "post",
+                    true,
+                    test_request(),
+                )
+            };
+
+            // The validator's rejection shouldn't use up the limit
+            assert_eq!(status(get("invalid").await), 404);
+            // Nor should a failed build
+            assert_eq!(status(get("broken").await), 500);
+            assert_eq!(store.read("incremental/post.json").await.unwrap(), "[]");
+
+            assert_eq!(status(get("a").await), 200);
+            // Pages that have already been generated don't count again
+            assert_eq!(status(get("a").await), 200);
+            assert_eq!(status(get("b").await), 404);
+            assert!(!store.exists("static/xx-XX-post%2Fb.json").await.unwrap());
+
+            // On-demand revalidation can't be used to get around any of this
+            let revalidate = |path: &str| {
+                turbine.revalidate(PathWithoutLocale(format!("post/{}", path)), "xx-XX")
+            };
+            assert_eq!(status(revalidate("invalid").await), 404);
+            assert_eq!(status(revalidate("b").await), 404);
+            assert!(!store.exists("static/xx-XX-post%2Fb.json").await.unwrap());
+            // But pages that have been generated can be revalidated
+            assert_eq!(status(revalidate("a").await), 200);
+        });
+    }
+}
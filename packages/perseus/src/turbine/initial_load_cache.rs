@@ -0,0 +1,205 @@
+use super::{build::get_full_path_without_locale, ApiResponse, Turbine};
+use crate::{i18n::TranslationsManager, path::PurePath, stores::MutableStore, template::Entity};
+use http::{HeaderMap, StatusCode};
+use std::{collections::HashMap, sync::Mutex};
+use sycamore::web::SsrNode;
+
+/// A bounded in-memory cache of the final responses to initial loads for pages
+/// that can't change from request to request, which avoids reading them from
+/// the stores and interpolating them into the HTML shell every time they're
+/// requested. When full, the least recently used page will be evicted.
+#[derive(Debug)]
+pub(super) struct InitialLoadCache {
+    /// The maximum number of pages that will be cached.
+    capacity: usize,
+    entries: Mutex<CacheEntries>,
+}
+
+#[derive(Debug, Default)]
+struct CacheEntries {
+    /// The cached responses, indexed by locale and path.
+    responses: HashMap<String, CachedResponse>,
+    /// A counter used to keep track of when each response was last used.
+    tick: u64,
+    /// A counter that's incremented every time something is removed from the
+    /// cache, so that responses generated before then can't be cached
+    /// afterward.
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    body: String,
+    headers: HeaderMap,
+    last_used: u64,
+}
+
+impl InitialLoadCache {
+    /// Creates a new cache that will hold at most the given number of pages.
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(CacheEntries::default()),
+        }
+    }
+    /// Gets the cached response for the given key, if there is one.
+    pub(super) fn get(&self, key: &str) -> Option<ApiResponse> {
+        let mut entries = self.lock();
+        entries.tick += 1;
+        let tick = entries.tick;
+        let cached = entries.responses.get_mut(key)?;
+        cached.last_used = tick;
+
+        Some(ApiResponse {
+            body: cached.body.clone(),
+            headers: cached.headers.clone(),
+            status: StatusCode::OK,
+        })
+    }
+    /// Gets the current generation of the cache. This should be taken before
+    /// generating a response, and provided to `.insert()` afterward.
+    pub(super) fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+    /// Caches the given response under the given key, provided it's a *200
+    /// OK* response and nothing has been removed from the cache since the given
+    /// generation (in which case the response might be stale).
+    pub(super) fn insert(&self, key: String, response: &ApiResponse, generation: u64) {
+        if self.capacity == 0 || response.status != StatusCode::OK {
+            return;
+        }
+
+        let mut entries = self.lock();
+        if entries.generation != generation {
+            return;
+        }
+        if entries.responses.len() >= self.capacity && !entries.responses.contains_key(&key) {
+            let lru_key = entries
+                .responses
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(lru_key) = lru_key {
+                entries.responses.remove(&lru_key);
+            }
+        }
+        entries.tick += 1;
+        let tick = entries.tick;
+        entries.responses.insert(
+            key,
+            CachedResponse {
+                body: response.body.clone(),
+                headers: response.headers.clone(),
+                last_used: tick,
+            },
+        );
+    }
+    /// Removes the response cached under the given key, if there is one. This
+    /// also prevents any responses currently being generated from being
+    /// cached, since they could have been generated from whatever made this
+    /// one stale.
+    pub(super) fn remove(&self, key: &str) {
+        let mut entries = self.lock();
+        entries.generation += 1;
+        entries.responses.remove(key);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheEntries> {
+        // Nothing we do with this can leave it inconsistent
+        self.entries.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Checks whether or not initial loads of pages from the given template
+    /// can be cached in memory, which is only possible if neither it nor the
+    /// global state depend on the request (including through a guard), and if
+    /// it doesn't revalidate.
+    /// (Whether or not the page depends on widgets that do these things can
+    /// only be determined once it's been rendered.)
+    pub(super) fn initial_load_is_cacheable(&self, entity: &Entity<SsrNode>) -> bool {
+        self.initial_load_cache.is_some()
+            && !entity.revalidates()
+            && !entity.uses_request_state()
+            && !entity.uses_guard()
+            && !self.global_state_creator.uses_request_state()
+    }
+    /// Removes the given page from the in-memory cache of initial loads, if
+    /// there is one. This should be called whenever a page/widget is rebuilt
+    /// at request-time.
+    ///
+    /// Only pages that were prerendered at build-time are ever cached, so
+    /// widgets can't affect them (their states were fixed at build-time), and
+    /// pages that can't be cached don't need to be removed.
+    pub(super) fn invalidate_initial_load(
+        &self,
+        path: &PurePath,
+        entity: &Entity<SsrNode>,
+        locale: &str,
+    ) {
+        if let Some(cache) = &self.initial_load_cache {
+            if !entity.is_capsule && self.initial_load_is_cacheable(entity) {
+                let full_path = get_full_path_without_locale(path, entity);
+                cache.remove(&format!("{}-{}", locale, full_path.0));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached_body(cache: &InitialLoadCache, key: &str) -> Option<String> {
+        cache.get(key).map(|res| res.body)
+    }
+
+    #[test]
+    fn should_evict_least_recently_used() {
+        let cache = InitialLoadCache::new(2);
+        cache.insert("en-US-a".to_string(), &ApiResponse::ok("a"), 0);
+        cache.insert("en-US-b".to_string(), &ApiResponse::ok("b"), 0);
+        // Using `a` makes `b` the least recently used
+        assert_eq!(cached_body(&cache, "en-US-a").as_deref(), Some("a"));
+        cache.insert("en-US-c".to_string(), &ApiResponse::ok("c"), 0);
+
+        assert_eq!(cached_body(&cache, "en-US-a").as_deref(), Some("a"));
+        assert_eq!(cached_body(&cache, "en-US-b"), None);
+        assert_eq!(cached_body(&cache, "en-US-c").as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn should_cache_nothing_with_zero_capacity() {
+        let cache = InitialLoadCache::new(0);
+        cache.insert("en-US-a".to_string(), &ApiResponse::ok("a"), 0);
+        assert_eq!(cached_body(&cache, "en-US-a"), None);
+    }
+
+    #[test]
+    fn should_not_cache_errors() {
+        let cache = InitialLoadCache::new(1);
+        let res = ApiResponse::err(StatusCode::INTERNAL_SERVER_ERROR, "error");
+        cache.insert("en-US-a".to_string(), &res, 0);
+        assert_eq!(cached_body(&cache, "en-US-a"), None);
+    }
+
+    #[test]
+    fn should_reject_stale_inserts_after_removal() {
+        let cache = InitialLoadCache::new(2);
+        cache.insert("en-US-a".to_string(), &ApiResponse::ok("a"), 0);
+        // This response started being generated before `a` was rebuilt
+        let generation = cache.generation();
+        cache.remove("en-US-a");
+        assert_eq!(cached_body(&cache, "en-US-a"), None);
+
+        cache.insert("en-US-a".to_string(), &ApiResponse::ok("stale"), generation);
+        assert_eq!(cached_body(&cache, "en-US-a"), None);
+        // But anything generated afterward is fine
+        cache.insert(
+            "en-US-a".to_string(),
+            &ApiResponse::ok("a"),
+            cache.generation(),
+        );
+        assert_eq!(cached_body(&cache, "en-US-a").as_deref(), Some("a"));
+    }
+}
@@ -6,17 +6,34 @@
 //! If you wanted to isolate the core of engine-side Perseus, it would be this
 //! module.
 
+mod background;
 mod build;
 mod build_error_page;
 mod export;
 mod export_error_page;
+mod health;
+mod incremental;
 mod initial_consts;
+mod initial_load_cache;
+mod revalidate;
+mod scheduler;
 mod serve;
 /// This has the actual API endpoints.
 mod server;
+mod single_flight;
+mod tags;
+#[cfg(test)]
+mod test_utils;
 mod tinker;
 
-pub use server::{ApiResponse, SubsequentLoadQueryParams};
+use background::BackgroundQueue;
+pub use health::HealthStatus;
+use initial_load_cache::InitialLoadCache;
+pub use scheduler::RevalidationSchedulerOptions;
+pub use server::{
+    ApiResponse, RevalidationQueryParams, StreamingApiResponse, SubsequentLoadQueryParams,
+};
+use single_flight::InFlightMap;
 
 use crate::{
     error_views::ErrorViews,
@@ -24,13 +41,18 @@ use crate::{
     i18n::{Locales, TranslationsManager},
     init::{PerseusAppBase, Tm},
     plugins::Plugins,
+    router::RouteRules,
     server::HtmlShell,
     state::{GlobalStateCreator, TemplateState},
     stores::{ImmutableStore, MutableStore},
     template::EntityMap,
 };
 use futures::executor::block_on;
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+};
 use sycamore::web::SsrNode;
 
 /// The Perseus state generator.
@@ -43,7 +65,7 @@ pub struct Turbine<M: MutableStore, T: TranslationsManager> {
     /// The app's locales data.
     locales: Locales,
     /// An immutable store.
-    immutable_store: ImmutableStore,
+    immutable_store: Arc<dyn ImmutableStore>,
     /// A mutable store.
     mutable_store: M,
     /// A translations manager.
@@ -58,6 +80,15 @@ pub struct Turbine<M: MutableStore, T: TranslationsManager> {
     pub static_dir: PathBuf,
     /// The app's static aliases.
     pub static_aliases: HashMap<String, String>,
+    /// The app's redirects and rewrites.
+    route_rules: RouteRules,
+    /// The maximum number of pages/widgets that will be built at once.
+    build_concurrency: Option<usize>,
+    /// An in-memory cache of initial loads for pages that can't change at
+    /// request-time, if the app has enabled one.
+    initial_load_cache: Option<InitialLoadCache>,
+    /// Whether or not requests for pages should be timed.
+    server_timing: bool,
     // --- These may not be populated at creation ---
     /// The app's render configuration, a map of paths in the app to the names
     /// of the templates that generated them. (Since templates can have
@@ -72,6 +103,26 @@ pub struct Turbine<M: MutableStore, T: TranslationsManager> {
     /// The HTML shell that can be used for constructing the full pages this app
     /// returns.
     html_shell: Option<HtmlShell>,
+    /// Whether or not the translations for every locale could be loaded when
+    /// the turbine was populated.
+    translations_loaded: bool,
+    /// Work that has been deferred until after responses have been sent.
+    background: BackgroundQueue,
+    /// The request-time generations currently in progress.
+    in_flight: InFlightMap,
+    /// A lock on the index of which pages and widgets use which cache tags.
+    tag_index_lock: futures::lock::Mutex<()>,
+    /// Whether or not anything could have been marked as invalidated, which
+    /// lets us avoid checking for invalidation markers on every request in
+    /// apps that don't use cache tags.
+    invalidations_possible: AtomicBool,
+    /// When we last checked the mutable store for whether or not anything
+    /// could have been invalidated, so we notice invalidations made by other
+    /// processes sharing it.
+    invalidations_checked_at: std::sync::Mutex<Option<std::time::Instant>>,
+    /// A lock on the indices of which pages have been incrementally generated
+    /// for each template.
+    incremental_index_lock: futures::lock::Mutex<()>,
 }
 
 // We want to be able to create a turbine straight from an app base
@@ -98,6 +149,10 @@ impl<M: MutableStore, T: TranslationsManager> TryFrom<PerseusAppBase<SsrNode, M,
             root_id,
             static_dir: PathBuf::from(&app.static_dir),
             static_aliases,
+            route_rules: app.route_rules,
+            build_concurrency: app.build_concurrency,
+            initial_load_cache: app.initial_load_cache.map(InitialLoadCache::new),
+            server_timing: app.server_timing,
             #[cfg(debug_assertions)]
             error_views: app.error_views.unwrap_or_default(),
             #[cfg(not(debug_assertions))]
@@ -116,6 +171,13 @@ impl<M: MutableStore, T: TranslationsManager> TryFrom<PerseusAppBase<SsrNode, M,
             // This will be immediately overriden
             global_state: TemplateState::empty(),
             html_shell: None,
+            translations_loaded: false,
+            background: BackgroundQueue::default(),
+            in_flight: InFlightMap::default(),
+            tag_index_lock: futures::lock::Mutex::new(()),
+            invalidations_possible: AtomicBool::new(false),
+            invalidations_checked_at: std::sync::Mutex::new(None),
+            incremental_index_lock: futures::lock::Mutex::new(()),
         })
     }
 }
@@ -148,6 +210,9 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         )
         .await?;
         self.html_shell = Some(html_shell);
+        self.translations_loaded = self.check_translations().await;
+
+        self.load_invalidations_possible().await?;
 
         Ok(())
     }
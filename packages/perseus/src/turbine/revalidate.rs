@@ -0,0 +1,130 @@
+use super::{build::get_full_path_without_locale, Turbine};
+use crate::{
+    errors::*,
+    i18n::TranslationsManager,
+    path::{PathWithoutLocale, PurePath},
+    router::{match_route, FullRouteInfo, FullRouteVerdict},
+    server::get_path_slice,
+    stores::MutableStore,
+    template::Entity,
+};
+use sycamore::web::SsrNode;
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Revalidates the page or widget at the given path in the given locale
+    /// immediately, regenerating its state, head, and prerendered HTML,
+    /// regardless of any revalidation timestamps or custom revalidation
+    /// logic. This is designed for when you know some data has changed,
+    /// such as when a CMS fires a webhook after something is published.
+    ///
+    /// The path given here should not include the locale. In apps that don't
+    /// use i18n, the locale should be `xx-XX`.
+    ///
+    /// Only pages/widgets whose templates/capsules revalidate, or which were
+    /// generated incrementally, can be revalidated on-demand, since everything
+    /// else lives in the immutable store. Incrementally generated pages that
+    /// haven't been generated yet (or that the mutable store has lost) will
+    /// give a *404 Not Found* error, since generating them here would bypass
+    /// the template's incremental path validator and page limit: they'll be
+    /// generated when they're next requested instead.
+    ///
+    /// Note that any global state used in the regeneration will be the
+    /// build-time global state, since there is no user request to generate
+    /// request-time global state from.
+    pub async fn revalidate(
+        &self,
+        path: PathWithoutLocale,
+        locale: &str,
+    ) -> Result<(), ServerError> {
+        let (pure_path, entity, was_incremental_match) =
+            self.route_for_revalidation(&path, locale)?;
+
+        if was_incremental_match {
+            let full_path = get_full_path_without_locale(&pure_path, entity);
+            let path_encoded = format!("{}-{}", locale, urlencoding::encode(&full_path));
+            if !self.has_stored_state(&path_encoded, entity).await? {
+                return Err(ServeError::PageNotFound {
+                    path: full_path.to_string(),
+                }
+                .into());
+            }
+        }
+
+        let build_extra = self.get_build_extra(entity).await?;
+        self.build_path_or_widget_for_locale(
+            pure_path.clone(),
+            entity,
+            &build_extra,
+            locale,
+            self.global_state.clone(),
+            false,
+            // Incremental pages always live in the mutable store (revalidating ones will go
+            // there anyway)
+            was_incremental_match,
+        )
+        .await?;
+        self.invalidate_initial_load(&pure_path, entity, locale);
+
+        Ok(())
+    }
+
+    /// Works out which entity the given path (without the locale) belongs to
+    /// in the given locale, checking that it can actually be revalidated.
+    /// This returns the path within that entity, the entity itself, and
+    /// whether or not the path was matched through incremental generation.
+    pub(super) fn route_for_revalidation(
+        &self,
+        path: &str,
+        locale: &str,
+    ) -> Result<(PurePath, &Entity<SsrNode>, bool), ServerError> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = path.strip_suffix('/').unwrap_or(path);
+        let not_found = || {
+            ServerError::from(ServeError::PageNotFound {
+                path: path.to_string(),
+            })
+        };
+        if !self.locales.is_supported(locale) {
+            return Err(not_found());
+        }
+
+        // Route the path just as we would for a user's request (the router expects the
+        // locale to be included if the app uses i18n)
+        let full_path = if self.locales.using_i18n {
+            format!("{}/{}", locale, path)
+        } else {
+            path.to_string()
+        };
+        let path_slice = get_path_slice(&full_path);
+        let verdict = match_route(&path_slice, &self.render_cfg, &self.entities, &self.locales);
+        let (path, entity, was_incremental_match) = match verdict.into_full(&self.entities) {
+            FullRouteVerdict::Found(FullRouteInfo {
+                path,
+                entity,
+                was_incremental_match,
+                ..
+            }) => (path, entity, was_incremental_match),
+            _ => return Err(not_found()),
+        };
+
+        if !(entity.revalidates() || was_incremental_match) {
+            return Err(ServeError::CannotRevalidate {
+                path: path.to_string(),
+            }
+            .into());
+        }
+
+        // The build logic expects the path within the entity
+        let entity_name = entity.get_path();
+        let pure_path = path
+            .strip_prefix(&entity_name)
+            .ok_or(ServerError::TemplateNameNotInPath)?;
+        let pure_path = pure_path.strip_prefix('/').unwrap_or(pure_path);
+
+        Ok((
+            PurePath(pure_path.to_string()),
+            entity,
+            was_incremental_match,
+        ))
+    }
+}
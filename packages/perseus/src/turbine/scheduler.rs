@@ -0,0 +1,154 @@
+use super::Turbine;
+use crate::{errors::*, i18n::TranslationsManager, stores::MutableStore};
+use chrono::{DateTime, Utc};
+use fmterr::fmt_err;
+use futures::{stream, StreamExt};
+use std::{collections::HashSet, time::Duration};
+
+/// The options for the background revalidation scheduler, which proactively
+/// revalidates pages and widgets that use time-based revalidation once their
+/// revalidation deadlines have passed, rather than waiting for someone to
+/// request them.
+#[derive(Debug, Clone)]
+pub struct RevalidationSchedulerOptions {
+    /// How often to check for pages and widgets that need revalidating.
+    pub interval: Duration,
+    /// The maximum number of pages and widgets that will be revalidated at
+    /// once.
+    pub concurrency: usize,
+}
+impl Default for RevalidationSchedulerOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            concurrency: 4,
+        }
+    }
+}
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Runs the background revalidation scheduler, which will check for pages
+    /// and widgets whose time-based revalidation deadlines have passed on
+    /// the given interval, and revalidate them ahead of time. This will
+    /// never return, so it should be run alongside your server (the default
+    /// servers in the integrations will do this for you if
+    /// `ServerOptions::revalidation_scheduler` is set).
+    ///
+    /// Any errors in revalidation will be reported through `tracing`, and the affected pages will
+    /// simply be tried again next time.
+    pub async fn run_revalidation_scheduler(&self, opts: RevalidationSchedulerOptions) {
+        loop {
+            if let Err(err) = self.revalidate_expired(opts.concurrency).await {
+                tracing::error!("scheduled revalidation failed: {}", fmt_err(&err));
+            }
+            tokio::time::sleep(opts.interval).await;
+        }
+    }
+
+    /// Revalidates every page and widget whose time-based revalidation
+    /// deadline has passed, with at most the given number being revalidated
+    /// at once. Errors in revalidating individual pages/widgets will be
+    /// reported through `tracing`, but won't stop the others from being
+    /// revalidated.
+    ///
+    /// Pages and widgets that also use logic-based revalidation will be
+    /// ignored, since that logic needs a request to run on. Any global state
+    /// used in revalidation will be the build-time global state.
+    ///
+    /// Incremental pages can only be found if the mutable store supports
+    /// listing assets.
+    pub async fn revalidate_expired(&self, concurrency: usize) -> Result<(), ServerError> {
+        // We need the path and locale of everything that could have a revalidation
+        // timestamp, starting with everything we knew about at build-time
+        let mut candidates = HashSet::new();
+        for (path, entity_name) in self.render_cfg.iter() {
+            let revalidates = self
+                .entities
+                .get(entity_name)
+                .is_some_and(|entity| entity.revalidates_with_time());
+            if revalidates {
+                for locale in self.locales.get_all() {
+                    candidates.insert((path.to_string(), locale.to_string()));
+                }
+            }
+        }
+        // Incremental pages aren't in the render configuration, so we'll find them
+        // through their timestamps
+        match self.mutable_store.list("static/").await {
+            Ok(names) => {
+                for name in names {
+                    if let Some(candidate) = self.decode_revalidation_timestamp_name(&name) {
+                        candidates.insert(candidate);
+                    }
+                }
+            }
+            Err(StoreError::NotSupported { .. }) => (),
+            Err(err) => return Err(err.into()),
+        }
+
+        stream::iter(candidates)
+            .for_each_concurrent(Some(concurrency.max(1)), |(path, locale)| async move {
+                if let Err(err) = self.revalidate_if_expired(&path, &locale).await {
+                    tracing::error!(
+                        "scheduled revalidation of '{}' in locale '{}' failed: {}",
+                        path,
+                        locale,
+                        fmt_err(&err)
+                    );
+                }
+            })
+            .await;
+
+        Ok(())
+    }
+
+    /// Revalidates the given page/widget if its revalidation deadline has
+    /// passed.
+    async fn revalidate_if_expired(&self, path: &str, locale: &str) -> Result<(), ServerError> {
+        let (pure_path, entity, was_incremental_match) =
+            self.route_for_revalidation(path, locale)?;
+        if !entity.revalidates_with_time() || entity.revalidates_with_logic() {
+            return Ok(());
+        }
+
+        let path_encoded = format!("{}-{}", locale, urlencoding::encode(path));
+        let datetime_to_revalidate_str = match self
+            .mutable_store
+            .read(&format!("static/{}.revld.txt", path_encoded))
+            .await
+        {
+            Ok(datetime_str) => datetime_str,
+            // This hasn't been generated yet (or the store has lost it), so we'll leave it
+            // to be generated when it's requested
+            Err(StoreError::NotFound { .. }) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        let datetime_to_revalidate = DateTime::parse_from_rfc3339(&datetime_to_revalidate_str)
+            .map_err(|err| ServerError::ServeError(ServeError::BadRevalidate { source: err }))?;
+        if datetime_to_revalidate > Utc::now() {
+            return Ok(());
+        }
+
+        let build_extra = self.get_build_extra(entity).await?;
+        self.build_path_or_widget_once(
+            pure_path,
+            entity,
+            &build_extra,
+            locale,
+            self.global_state.clone(),
+            was_incremental_match,
+        )
+        .await
+    }
+
+    /// Converts the name of a revalidation timestamp in the mutable store back
+    /// into the path and locale of the page/widget it's for.
+    fn decode_revalidation_timestamp_name(&self, name: &str) -> Option<(String, String)> {
+        let path_encoded = name.strip_prefix("static/")?.strip_suffix(".revld.txt")?;
+        self.locales.get_all().into_iter().find_map(|locale| {
+            let path = path_encoded.strip_prefix(&format!("{}-", locale))?;
+            let path = urlencoding::decode(path).ok()?;
+            Some((path.to_string(), locale.to_string()))
+        })
+    }
+}
@@ -1,19 +1,20 @@
 use chrono::{DateTime, Utc};
 use fmterr::fmt_err;
-use futures::{
-    future::{try_join_all, BoxFuture},
-    FutureExt,
-};
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
 use serde_json::Value;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 use sycamore::web::SsrNode;
 
-use super::Turbine;
+use super::{background::RevalidationJob, Turbine};
 use crate::{
     error_views::ServerErrorData,
     reactor::RenderMode,
     router::{match_route, FullRouteVerdict},
-    template::Entity,
+    template::{Entity, GuardVerdict},
 };
 use crate::{
     errors::*,
@@ -21,21 +22,21 @@ use crate::{
     internal::{PageData, PageDataPartial},
     path::*,
     server::get_path_slice,
-    state::StateGeneratorInfo,
+    state::{CacheTags, StateGeneratorInfo},
     stores::MutableStore,
     template::States,
     Request,
 };
 use crate::{
     state::{TemplateState, UnknownStateType},
-    utils::ssr_fallible,
+    utils::{get_path_prefix_server, ssr_fallible, time_phase, time_phase_sync},
 };
 
 /// This is `PageDataPartial`, but it keeps the state as `TemplateState` for
 /// internal convenience.
-struct StateAndHead {
-    state: TemplateState,
-    head: String,
+pub(super) struct StateAndHead {
+    pub(super) state: TemplateState,
+    pub(super) head: String,
 }
 
 impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
@@ -88,19 +89,13 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
     /// state in a tuple.
     ///
     /// # Pitfalls
-    /// This currently uses a layer-based dependency resolution algorithm, as a
-    /// widget may itself have widgets. However, the widgets a page/widget
-    /// uses may be dependent on its state, and therefore we cannot enumerate
-    /// the entire dependency tree without knowing all the states involved.
-    /// Therefore, we go layer-by-layer. Currently, we wait for each layer
-    /// to be fully complete before proceeding to the next one, which leads to
-    /// a layer taking as long as the longest state generation within it. This
-    /// can lead to poor render times when widgets are highly nested, a
-    /// pattern that should be avoided as much as possible.
-    ///
-    /// In future, this will build with maximal parallelism by not waiting for
-    /// each layer to be finished building before proceeding to the next
-    /// one.
+    /// A widget may itself have widgets, and the widgets a page/widget uses may
+    /// be dependent on its state, so we cannot enumerate the entire dependency
+    /// tree without knowing all the states involved. Instead, each widget's
+    /// state is generated as soon as the widget using it has been resolved,
+    /// without waiting for anything else. However, this means the page will
+    /// be rendered again every time a widget is resolved, which can lead to
+    /// poor render times for pages with a very large number of widgets.
     pub async fn get_initial_load_for_path(
         &self,
         path: PathWithoutLocale,
@@ -109,23 +104,74 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         was_incremental: bool,
         req: Request,
     ) -> Result<(PageData, TemplateState), ServerError> {
-        let locale = translator.get_locale();
+        let (page_state, global_state) = self
+            .get_initial_state_for_path(path.clone(), translator, template, was_incremental, &req)
+            .await?;
+        let page_data = self
+            .render_initial_load_for_path(
+                path,
+                translator,
+                template,
+                page_state,
+                global_state.clone(),
+                &req,
+            )
+            .await?
+            .0;
+
+        Ok((page_data, global_state))
+    }
+    /// Generates the state and head of the given page for an initial load,
+    /// along with the most up-to-date global state, without rendering the
+    /// page itself. This is the first half of `.get_initial_load_for_path()`,
+    /// and it's separated out so that streaming initial loads can send the
+    /// start of the HTML shell once they know the page exists (and what its
+    /// headers will be), but before resolving its widgets.
+    pub(super) async fn get_initial_state_for_path(
+        &self,
+        path: PathWithoutLocale,
+        translator: &Translator,
+        template: &Entity<SsrNode>,
+        was_incremental: bool,
+        req: &Request,
+    ) -> Result<(StateAndHead, TemplateState), ServerError> {
         // Get the latest global state, which we'll share around
-        let global_state = self.get_full_global_state(clone_req(&req)).await?;
+        let global_state = self.get_full_global_state(clone_req(req)).await?;
         // Begin by generating the state for this page
         let page_state = self
             .get_state_for_path_internal(
-                path.clone(),
+                path,
                 translator,
                 &template.get_path(),
                 was_incremental,
-                clone_req(&req),
+                clone_req(req),
                 Some(template),
                 Some(global_state.clone()),
                 true, // This is an initial load
             )
             .await?;
 
+        Ok((page_state, global_state))
+    }
+    /// Renders the given page for an initial load, given its state and the
+    /// global state from `.get_initial_state_for_path()`, resolving all its
+    /// widget dependencies. This is the second half of
+    /// `.get_initial_load_for_path()`.
+    ///
+    /// This also returns whether or not the page was prerendered at
+    /// build-time, which means none of its widgets can change at
+    /// request-time.
+    pub(super) async fn render_initial_load_for_path(
+        &self,
+        path: PathWithoutLocale,
+        translator: &Translator,
+        template: &Entity<SsrNode>,
+        page_state: StateAndHead,
+        global_state: TemplateState,
+        req: &Request,
+    ) -> Result<(PageData, bool), ServerError> {
+        let locale = translator.get_locale();
+
         let path = PathWithoutLocale(path.strip_suffix('/').unwrap_or(&*path).to_string());
         // Yes, this is created twice; no, we don't care
         // If we're interacting with the stores, this is the path this page/widget will
@@ -157,19 +203,30 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             Err(StoreError::NotFound { .. }) => None,
             Err(err) => return Err(err.into()),
         };
+        // If there was a prerendered fragment, there will also be a record of the
+        // widget states we need to send to the client (unless a mutable store has
+        // evicted it, in which case we'll just render again)
+        let prerendered = match prerendered_fragment {
+            Some(prerendered_fragment) => {
+                let widget_states_res = if template.revalidates() {
+                    self.mutable_store
+                        .read(&format!("static/{}.widgets.json", &path_encoded))
+                        .await
+                } else {
+                    self.immutable_store
+                        .read(&format!("static/{}.widgets.json", &path_encoded))
+                        .await
+                };
+                match widget_states_res {
+                    Ok(widget_states) => Some((prerendered_fragment, widget_states)),
+                    Err(StoreError::NotFound { .. }) if template.revalidates() => None,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            None => None,
+        };
 
-        if let Some(prerendered_fragment) = prerendered_fragment {
-            // If there was a prerendered fragment, there will also be a record of the
-            // widget states we need to send to the client
-            let widget_states = if template.revalidates() {
-                self.mutable_store
-                    .read(&format!("static/{}.widgets.json", &path_encoded))
-                    .await?
-            } else {
-                self.immutable_store
-                    .read(&format!("static/{}.widgets.json", &path_encoded))
-                    .await?
-            };
+        if let Some((prerendered_fragment, widget_states)) = prerendered {
             // From the build process, these are infallible
             let widget_states = match serde_json::from_str::<
                 HashMap<PathMaybeWithLocale, (String, Value)>,
@@ -190,21 +247,20 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                         .map(|(k, (_, v))| (k, Ok(v)))
                         .collect(),
                 },
-                global_state,
+                true,
             ))
         } else {
             // This will block
             let (final_widget_states, prerendered) = self
                 .render_all(
-                    HashMap::new(), // This starts empty
                     path,
                     locale.to_string(),
                     page_state.state.clone(),
                     template,
-                    global_state.clone(),
-                    &req,
+                    global_state,
+                    req,
                     translator,
-                )?
+                )
                 .await?;
             // Convert the `TemplateState`s into `Value`s
             let final_widget_states = final_widget_states
@@ -220,69 +276,126 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                     state: page_state.state.state,
                     widget_states: final_widget_states,
                 },
-                global_state,
+                false,
             ))
         }
     }
-    /// Recurses through each layer of dependencies and eventually renders the
-    /// given page.
+    /// Resolves all the widget dependencies of the given page/widget and
+    /// renders it.
     ///
-    /// This returns a tuple of widget states and the prerendered result.
+    /// This works by rendering the page with whatever widget states we have,
+    /// which will tell us about any widgets that haven't yet been resolved.
+    /// Their states are generated concurrently, and, as soon as any of them
+    /// is ready, we render again to discover any widgets *it* uses, whose
+    /// states will start being generated immediately. That way, no widget
+    /// has to wait for anything other than its ancestors, though this does
+    /// mean we render once for every widget-resolution (or batch of them, if
+    /// several finish at once), rather than once per layer.
     ///
-    /// This is deliberately synchronous to avoid making `Self` `Sync`, which is
-    /// impossible with Perseus' current design. Thus, this blocks when
-    /// resolving each layer.
+    /// This returns a tuple of widget states and the prerendered result.
     #[allow(clippy::too_many_arguments)]
     #[allow(clippy::type_complexity)]
-    fn render_all<'a>(
-        &'a self,
-        // This is a map of widget paths to their states, which we'll populate as
-        // we go through. That way, we can just run the exact same render over and over
-        // again, getting to a new layer each time, since, if a widget finds its state in
-        // this, it'll use it. This will be progressively accumulated over many layers.
-        widget_states: HashMap<PathMaybeWithLocale, Result<TemplateState, ServerErrorData>>,
+    async fn render_all(
+        &self,
         path: PathWithoutLocale,
         locale: String,
         state: TemplateState,
-        entity: &'a Entity<SsrNode>, // Recursion could make this either a template or a capsule
+        entity: &Entity<SsrNode>, // Recursion could make this either a template or a capsule
         global_state: TemplateState,
-        req: &'a Request,
-        translator: &'a Translator,
+        req: &Request,
+        translator: &Translator,
     ) -> Result<
-        BoxFuture<
-            'a,
-            Result<
-                (
-                    HashMap<PathMaybeWithLocale, Result<TemplateState, ServerErrorData>>,
-                    String,
-                ),
-                ServerError,
-            >,
-        >,
+        (
+            HashMap<PathMaybeWithLocale, Result<TemplateState, ServerErrorData>>,
+            String,
+        ),
         ServerError,
     > {
         // Misleadingly, this only has the locale if we're using i18n!
         let full_path = PathMaybeWithLocale::new(&path, &locale);
 
+        // This is a map of widget paths to their states, which we'll populate as
+        // we go through. That way, we can just run the exact same render over and over
+        // again, resolving more widgets each time, since, if a widget finds its state
+        // in this, it'll use it.
+        let mut widget_states = HashMap::new();
+        // Every widget whose state we've started generating (it's quite possible for
+        // a widget to be reported as unresolved again while it's still being built)
+        let mut requested = HashSet::new();
+        let mut pending = FuturesUnordered::new();
+        loop {
+            let (prerendered, unresolved) = time_phase_sync("render", || {
+                self.render_with_widget_states(
+                    &widget_states,
+                    &full_path,
+                    &state,
+                    entity,
+                    &global_state,
+                    translator,
+                )
+            })?;
+            for widget_path in unresolved {
+                if requested.insert(widget_path.clone()) {
+                    pending.push(time_phase(
+                        "widget_state",
+                        self.get_widget_state_for_render(
+                            widget_path,
+                            &locale,
+                            global_state.clone(),
+                            req,
+                            translator,
+                        ),
+                    ));
+                }
+            }
+
+            // If there's nothing left to resolve, that render was the final one
+            let (widget_path, res) = match pending.next().await {
+                Some(res) => res?,
+                None => return Ok((widget_states, prerendered)),
+            };
+            widget_states.insert(widget_path, res);
+            // If any other widgets have finished in the meantime, we may as well put them
+            // in the next render too
+            while let Some(Some(res)) = pending.next().now_or_never() {
+                let (widget_path, res) = res?;
+                widget_states.insert(widget_path, res);
+            }
+        }
+    }
+    /// Renders the given page/widget once, with the given widget states,
+    /// returning the prerendered result and the paths of any widgets that
+    /// weren't in those states.
+    ///
+    /// This is deliberately synchronous, since the render needs `Rc`s that
+    /// can't be held across `.await`s.
+    fn render_with_widget_states(
+        &self,
+        widget_states: &HashMap<PathMaybeWithLocale, Result<TemplateState, ServerErrorData>>,
+        full_path: &PathMaybeWithLocale,
+        state: &TemplateState,
+        entity: &Entity<SsrNode>,
+        global_state: &TemplateState,
+        translator: &Translator,
+    ) -> Result<(String, Vec<PathWithoutLocale>), ServerError> {
         // We put this in an `Rc` so it can be put in the context and given to multiple
-        // widgets, but it will never be changed (we could have a lot of states
-        // here, so we want to minimize cloning where possible)
-        let widget_states_rc = Rc::new(widget_states);
+        // widgets, but it will never be changed
+        // TODO Avoid cloning here...
+        let widget_states_rc = Rc::new(widget_states.clone());
         // This will be used to store the paths of widgets that haven't yet been
-        // resolved. It will be cleared between layers.
+        // resolved
         let unresolved_widget_accumulator = Rc::new(RefCell::new(Vec::new()));
         // Now we want to render the page in the dependency resolution mode (as opposed
         // to the build mode, which just cancels the render if it finds any
         // non-build-safe widgets).
         let mode = RenderMode::Request {
-            widget_states: widget_states_rc.clone(),
+            widget_states: widget_states_rc,
             error_views: self.error_views.clone(),
             unresolved_widget_accumulator: unresolved_widget_accumulator.clone(),
         };
 
-        // Start the first render. This registers all our mode stuff on `cx`,
-        // which is dropped when this is done. So, we can safely get the widget states
-        // back.
+        // This registers all our mode stuff on `cx`, which is dropped when this is
+        // done. So, we can safely get the unresolved widgets back.
         // Now prerender the actual content (a bit roundabout for error handling)
         let prerendered = ssr_fallible(|cx| {
             entity.render_for_template_server(
@@ -294,118 +407,82 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                 translator,
             )
         })?;
-        // // As explained above, this should never fail, because all references have
-        // been // dropped
-        // let mut widget_states = Rc::try_unwrap(widget_states_rc).unwrap();
-        // TODO Avoid cloning here...
-        let mut widget_states = (*widget_states_rc).clone();
-
-        // We'll just have accumulated a ton of unresolved widgets, probably. If not,
-        // then we're done! If yes, we'll need to build all their states.
         // TODO ...and here
-        let mut accumulator = (*unresolved_widget_accumulator).clone().into_inner();
+        let unresolved = (*unresolved_widget_accumulator).clone().into_inner();
 
-        let fut = async move {
-            if accumulator.is_empty() {
-                Ok((widget_states, prerendered))
-            } else {
-                // First, deduplicate (relevant if the same widget is used more than once). We
-                // don't care about unstable sorting because these are strings.
-                accumulator.sort_unstable();
-                accumulator.dedup();
-
-                let mut futs = Vec::new();
-                for widget_path in accumulator.into_iter() {
-                    let global_state = global_state.clone();
-                    let locale = locale.clone();
-                    futs.push(async move {
-                        // Resolve the route
-                        // Get a route verdict to determine the capsule this widget path maps to
-                        let localized_widget_path = PathMaybeWithLocale::new(&widget_path, &locale);
-                        let path_slice = get_path_slice(&localized_widget_path);
-                        let verdict = match_route(
-                            &path_slice,
-                            &self.render_cfg,
-                            &self.entities,
-                            &self.locales,
-                        );
-
-                        let res = match verdict.into_full(&self.entities) {
-                            FullRouteVerdict::Found(route_info) => {
-                                let capsule_name = route_info.entity.get_path();
-
-                                // Now build the state; if this fails, we won't fail the whole
-                                // page, we'll just load an error for this particular widget
-                                // (allowing the user to still see the rest of the page). If this
-                                // sort of thing were to happen in a subsequent load, the browser
-                                // would be responsible for this.
-                                self.get_state_for_path_internal(
-                                    widget_path.clone(),
-                                    translator,
-                                    &capsule_name,
-                                    route_info.was_incremental_match,
-                                    clone_req(req),
-                                    // We do happen to actually have this from the routing
-                                    Some(route_info.entity),
-                                    Some(global_state),
-                                    true, /* This is an initial load, so don't put an `Ok` in
-                                           * the `Value` */
-                                )
-                                .await
-                                // The error handling systems will need a client-style error,
-                                // so we just make the same conversion that would be made on
-                                // the browser-side
-                                .map_err(|err| ServerErrorData {
-                                    status: err_to_status_code(&err),
-                                    msg: fmt_err(&err),
-                                })
-                                // And discard the head (it's a widget)
-                                .map(|state| state.state)
-                            }
-                            // This is just completely wrong, and implies a corruption, so it's made
-                            // a page-level error
-                            FullRouteVerdict::LocaleDetection(_) => {
-                                return Err(ServerError::ResolveDepLocaleRedirection {
-                                    locale: locale.to_string(),
-                                    widget: widget_path.to_string(),
-                                })
-                            }
-                            // But a widget that isn't found will be made a widget-only error
-                            FullRouteVerdict::NotFound { .. } => {
-                                let err = ServerError::ResolveDepNotFound {
-                                    locale: locale.to_string(),
-                                    widget: widget_path.to_string(),
-                                };
-                                Err(ServerErrorData {
-                                    status: err_to_status_code(&err),
-                                    msg: fmt_err(&err),
-                                })
-                            }
-                        };
-
-                        // Return the tuples that'll go into `widget_states`
-                        Ok((localized_widget_path, res))
-                    });
-                }
-                let tuples = try_join_all(futs).await?;
-                widget_states.extend(tuples);
+        Ok((prerendered, unresolved))
+    }
+    /// Generates the state of the given widget for `.render_all()`, returning
+    /// it with the widget's localized path.
+    ///
+    /// If this fails, we won't fail the whole page, we'll just return an
+    /// error for this particular widget (allowing the user to still see the
+    /// rest of the page). If this sort of thing were to happen in a
+    /// subsequent load, the browser would be responsible for this.
+    async fn get_widget_state_for_render(
+        &self,
+        widget_path: PathWithoutLocale,
+        locale: &str,
+        global_state: TemplateState,
+        req: &Request,
+        translator: &Translator,
+    ) -> Result<(PathMaybeWithLocale, Result<TemplateState, ServerErrorData>), ServerError> {
+        // Resolve the route
+        // Get a route verdict to determine the capsule this widget path maps to
+        let localized_widget_path = PathMaybeWithLocale::new(&widget_path, locale);
+        let path_slice = get_path_slice(&localized_widget_path);
+        let verdict = match_route(&path_slice, &self.render_cfg, &self.entities, &self.locales);
 
-                // We've rendered this layer, and we're ready for the next one
-                self.render_all(
-                    widget_states,
-                    path,
-                    locale,
-                    state,
-                    entity,
-                    global_state,
-                    req,
+        let res = match verdict.into_full(&self.entities) {
+            FullRouteVerdict::Found(route_info) => {
+                let capsule_name = route_info.entity.get_path();
+
+                self.get_state_for_path_internal(
+                    widget_path.clone(),
                     translator,
-                )?
+                    &capsule_name,
+                    route_info.was_incremental_match,
+                    clone_req(req),
+                    // We do happen to actually have this from the routing
+                    Some(route_info.entity),
+                    Some(global_state),
+                    true, /* This is an initial load, so don't put an `Ok` in
+                           * the `Value` */
+                )
                 .await
+                // The error handling systems will need a client-style error,
+                // so we just make the same conversion that would be made on
+                // the browser-side
+                .map_err(|err| ServerErrorData {
+                    status: err_to_status_code(&err),
+                    msg: fmt_err(&err),
+                })
+                // And discard the head (it's a widget)
+                .map(|state| state.state)
             }
-        }
-        .boxed();
-        Ok(fut)
+            // This is just completely wrong, and implies a corruption, so it's made
+            // a page-level error
+            FullRouteVerdict::LocaleDetection(_) => {
+                return Err(ServerError::ResolveDepLocaleRedirection {
+                    locale: locale.to_string(),
+                    widget: widget_path.to_string(),
+                })
+            }
+            // But a widget that isn't found will be made a widget-only error
+            FullRouteVerdict::NotFound { .. } => {
+                let err = ServerError::ResolveDepNotFound {
+                    locale: locale.to_string(),
+                    widget: widget_path.to_string(),
+                };
+                Err(ServerErrorData {
+                    status: err_to_status_code(&err),
+                    msg: fmt_err(&err),
+                })
+            }
+        };
+
+        // Return the tuple that'll go into the widget states
+        Ok((localized_widget_path, res))
     }
 
     /// The internal version allows sharing a global state so we don't
@@ -479,33 +556,13 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
 
         // No matter what we end up doing, we're probably going to need this (which will
         // always exist)
-        let build_extra = match self
-            .immutable_store
-            .read(&format!(
-                "static/{}.extra.json",
-                urlencoding::encode(&entity.get_path())
-            ))
-            .await
-        {
-            Ok(state) => {
-                TemplateState::from_str(&state).map_err(|err| ServerError::InvalidBuildExtra {
-                    template_name: entity.get_path(),
-                    source: err,
-                })?
-            }
-            // If this happens, then the immutable store has been tampered with, since
-            // the build logic generates some kind of state for everything
-            Err(_) => {
-                return Err(ServerError::MissingBuildExtra {
-                    template_name: entity.get_path(),
-                })
-            }
-        };
+        let build_extra = self.get_build_extra(entity).await?;
         // We'll need this too for any sort of state generation
         let build_info = StateGeneratorInfo {
             path: path.to_string(),
             locale: locale.to_string(),
             extra: build_extra.clone(),
+            cache_tags: CacheTags::default(),
         };
 
         // The aim of this next block is purely to ensure that whatever is in the
@@ -553,13 +610,14 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                 if should_revalidate {
                     // We need to rebuild, which we can do with the build-time logic (which will use
                     // the mutable store)
-                    self.build_path_or_widget_for_locale(
+                    self.revalidate_for_request(
+                        &path_encoded,
                         pure_path,
+                        entity_name,
                         entity,
                         &build_extra,
                         &locale,
                         global_state.clone(),
-                        false,
                         true,
                     )
                     .await?;
@@ -574,37 +632,58 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                 // dependencies aren't build-safe. Of course, we can guarantee if we're actually
                 // generating it now that it won't be revalidating.
                 // We can provide the most up-to-date global state to this.
-                self.build_path_or_widget_for_locale(
-                    pure_path,
+                //
+                // Before that though, we need to make sure the user actually wants this page
+                // generated (this will 404 if not)
+                self.reserve_incremental_page(
                     entity,
-                    &build_extra,
-                    &locale,
-                    global_state.clone(),
-                    false,
-                    // This makes sure we use the mutable store no matter what (incremental)
-                    true,
-                )
-                .await?;
-            }
-        } else {
-            let should_revalidate = self
-                .page_or_widget_should_revalidate(
                     &path_encoded,
-                    entity,
                     build_info.clone(),
                     clone_req(&req),
                 )
                 .await?;
+                let res = self
+                    .build_path_or_widget_once(
+                        pure_path,
+                        entity,
+                        &build_extra,
+                        &locale,
+                        global_state.clone(),
+                        // This makes sure we use the mutable store no matter what (incremental)
+                        true,
+                    )
+                    .await;
+                if let Err(err) = res {
+                    self.release_incremental_page(entity, &path_encoded).await?;
+                    return Err(err);
+                }
+            }
+        } else {
+            // If the mutable store has lost this page (e.g. it's in-memory and the server
+            // restarted, or it was evicted), it has to be regenerated, no matter what
+            let should_revalidate =
+                if entity.revalidates() && !self.has_stored_state(&path_encoded, entity).await? {
+                    true
+                } else {
+                    self.page_or_widget_should_revalidate(
+                        &path_encoded,
+                        entity,
+                        build_info.clone(),
+                        clone_req(&req),
+                    )
+                    .await?
+                };
             if should_revalidate {
                 // We need to rebuild, which we can do with the build-time logic
-                self.build_path_or_widget_for_locale(
+                self.revalidate_for_request(
+                    &path_encoded,
                     pure_path,
+                    entity_name,
                     entity,
                     &build_extra,
                     &locale,
                     global_state.clone(),
                     false,
-                    false,
                 )
                 .await?;
             } else {
@@ -633,9 +712,11 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         // Now get the request state if we're using it (of course, this must be
         // re-generated for every request)
         let request_state = if entity.uses_request_state() {
-            entity
-                .get_request_state(build_info.clone(), clone_req(&req))
-                .await?
+            time_phase(
+                "request_state",
+                entity.get_request_state(build_info.clone(), clone_req(&req)),
+            )
+            .await?
         } else {
             TemplateState::empty()
         };
@@ -646,9 +727,11 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
             request_state,
         };
         let final_state = if states.both_defined() && entity.can_amalgamate_states() {
-            entity
-                .amalgamate_states(build_info, states.build_state, states.request_state)
-                .await?
+            time_phase(
+                "amalgamation",
+                entity.amalgamate_states(build_info, states.build_state, states.request_state),
+            )
+            .await?
         } else if states.both_defined() && !entity.can_amalgamate_states() {
             // We have both states, but can't amalgamate, so prioritze request state, as
             // it's more personalized and more recent
@@ -708,6 +791,150 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         })
     }
 
+    /// Runs the guard of the given template on the given request, returning
+    /// the URL the user should be redirected to if the guard redirected them,
+    /// or an error if it denied the request. Templates without guards allow
+    /// everything.
+    pub(super) async fn check_guard(
+        &self,
+        path: &PathWithoutLocale,
+        locale: &str,
+        entity: &Entity<SsrNode>,
+        req: &Request,
+    ) -> Result<Option<String>, ServerError> {
+        if !entity.uses_guard() {
+            return Ok(None);
+        }
+
+        let path = path.strip_suffix('/').unwrap_or(path).to_string();
+        let info = StateGeneratorInfo {
+            path: path.clone(),
+            locale: locale.to_string(),
+            extra: self.get_build_extra(entity).await?,
+            cache_tags: CacheTags::default(),
+        };
+        let verdict = time_phase("guard", entity.run_guard(info, clone_req(req))).await?;
+        match verdict {
+            GuardVerdict::Allow => Ok(None),
+            GuardVerdict::Deny(status) => Err(ServeError::GuardDenied {
+                path,
+                template_name: entity.get_path(),
+                // Anything that isn't an error code would be meaningless here
+                status: if (400..600).contains(&status) {
+                    status
+                } else {
+                    403
+                },
+            }
+            .into()),
+            GuardVerdict::Redirect(dest) => {
+                Ok(Some(format!("{}{}", get_path_prefix_server(), dest)))
+            }
+        }
+    }
+    /// Gets the extra build state that was generated for the given entity at
+    /// build-time.
+    pub(super) async fn get_build_extra(
+        &self,
+        entity: &Entity<SsrNode>,
+    ) -> Result<TemplateState, ServerError> {
+        match self
+            .immutable_store
+            .read(&format!(
+                "static/{}.extra.json",
+                urlencoding::encode(&entity.get_path())
+            ))
+            .await
+        {
+            Ok(state) => {
+                TemplateState::from_str(&state).map_err(|err| ServerError::InvalidBuildExtra {
+                    template_name: entity.get_path(),
+                    source: err,
+                })
+            }
+            // If this happens, then the immutable store has been tampered with, since
+            // the build logic generates some kind of state for everything
+            Err(_) => Err(ServerError::MissingBuildExtra {
+                template_name: entity.get_path(),
+            }),
+        }
+    }
+    /// Revalidates the given page/widget in response to a request that found it
+    /// to be stale. If the entity revalidates in the background, this will
+    /// try to defer the revalidation, meaning the stale version in the
+    /// mutable store will be served this time (if the mutable store has lost
+    /// that, the revalidation will be performed now).
+    #[allow(clippy::too_many_arguments)] // Internal function
+    async fn revalidate_for_request(
+        &self,
+        path_encoded: &str,
+        pure_path: PurePath,
+        entity_name: &str,
+        entity: &Entity<SsrNode>,
+        build_extra: &TemplateState,
+        locale: &str,
+        global_state: TemplateState,
+        was_incremental: bool,
+    ) -> Result<(), ServerError> {
+        // We can only defer this if there's a stale version to serve in the meantime
+        if entity.revalidates_in_background() && self.has_stored_state(path_encoded, entity).await?
+        {
+            let job = RevalidationJob {
+                key: path_encoded.to_string(),
+                entity_name: entity_name.to_string(),
+                path: pure_path.clone(),
+                locale: locale.to_string(),
+                extra: build_extra.clone(),
+                global_state: global_state.clone(),
+                was_incremental,
+            };
+            if self.queue_background_revalidation(job) {
+                return Ok(());
+            }
+            // If there's nothing running background tasks, we'll have to do this now
+        }
+
+        self.build_path_or_widget_once(
+            pure_path,
+            entity,
+            build_extra,
+            locale,
+            global_state,
+            was_incremental,
+        )
+        .await?;
+
+        Ok(())
+    }
+    /// Checks whether or not the mutable store has everything that will be
+    /// read from it to serve the given page/widget without generating it
+    /// again (its state, and its head if it has one that doesn't depend on
+    /// the request).
+    pub(super) async fn has_stored_state(
+        &self,
+        path_encoded: &str,
+        entity: &Entity<SsrNode>,
+    ) -> Result<bool, ServerError> {
+        if entity.uses_build_state()
+            && !self
+                .mutable_store
+                .exists(&format!("static/{}.json", path_encoded))
+                .await?
+        {
+            return Ok(false);
+        }
+        if !entity.is_capsule
+            && !entity.uses_request_state()
+            && !self
+                .mutable_store
+                .exists(&format!("static/{}.head.html", path_encoded))
+                .await?
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
     /// Checks timestamps and runs user-provided logic to determine if the given
     /// widget/path should revalidate at the present time.
     async fn page_or_widget_should_revalidate(
@@ -717,27 +944,47 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         build_info: StateGeneratorInfo<UnknownStateType>,
         req: Request,
     ) -> Result<bool, ServerError> {
+        // Anything that's been invalidated through a cache tag needs to be revalidated,
+        // no matter what (but only things in the mutable store can be)
+        if (entity.revalidates() || entity.uses_incremental())
+            && self.is_invalidated(path_encoded).await?
+        {
+            return Ok(true);
+        }
+
         let mut should_revalidate = false;
         // If it revalidates after a certain period of time, we need to check that
         // BEFORE the custom logic (clearly documented)
         if entity.revalidates_with_time() {
             // Get the time when it should revalidate (RFC 3339)
             // This will be updated, so it's in a mutable store
-            let datetime_to_revalidate_str = self
+            let datetime_to_revalidate_str = match self
                 .mutable_store
                 .read(&format!("static/{}.revld.txt", path_encoded))
-                .await?;
-            let datetime_to_revalidate = DateTime::parse_from_rfc3339(&datetime_to_revalidate_str)
-                .map_err(|err| {
-                    ServerError::ServeError(ServeError::BadRevalidate { source: err })
-                })?;
-            // Get the current time (UTC)
-            let now = Utc::now();
-
-            // If the datetime to revalidate is still in the future, end with `false` (the
-            // custom logic is only executed if the time-based one passes)
-            if datetime_to_revalidate > now {
-                return Ok(false);
+                .await
+            {
+                Ok(datetime_str) => Some(datetime_str),
+                // If the timestamp is missing, the mutable store has lost it (e.g. it's
+                // in-memory and the server restarted, or it was evicted), so we can't
+                // trust anything else in there for this page either
+                Err(StoreError::NotFound { .. }) => None,
+                Err(err) => return Err(err.into()),
+            };
+            if let Some(datetime_to_revalidate_str) = datetime_to_revalidate_str {
+                let datetime_to_revalidate =
+                    DateTime::parse_from_rfc3339(&datetime_to_revalidate_str).map_err(|err| {
+                        ServerError::ServeError(ServeError::BadRevalidate { source: err })
+                    })?;
+                // Get the current time (UTC)
+                let now = Utc::now();
+
+                // If the datetime to revalidate is still in the future, end with `false`
+                // (the custom logic is only executed if the time-based one passes)
+                if datetime_to_revalidate > now {
+                    return Ok(false);
+                }
+            } else {
+                return Ok(true);
             }
             should_revalidate = true;
         }
@@ -761,19 +1008,21 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         let built_state = &self.global_state;
 
         let global_state = if gsc.uses_request_state() {
-            let req_state = gsc.get_request_state(req).await?;
-            // If we have a non-empty build-time state, we'll need to amalgamate
-            if !built_state.is_empty() {
-                if gsc.can_amalgamate_states() {
-                    gsc.amalgamate_states(built_state.clone(), req_state)
-                        .await?
+            time_phase("global_state", async {
+                let req_state = gsc.get_request_state(req).await?;
+                // If we have a non-empty build-time state, we'll need to amalgamate
+                if !built_state.is_empty() {
+                    if gsc.can_amalgamate_states() {
+                        gsc.amalgamate_states(built_state.clone(), req_state).await
+                    } else {
+                        // No amalgamation capability, request time state takes priority
+                        Ok(req_state)
+                    }
                 } else {
-                    // No amalgamation capability, request time state takes priority
-                    req_state
+                    Ok(req_state)
                 }
-            } else {
-                req_state
-            }
+            })
+            .await?
         } else {
             // This global state is purely generated at build-time (or nonexistent)
             built_state.clone()
@@ -801,3 +1050,116 @@ fn clone_req(raw: &Request) -> Request {
         .body(())
         .unwrap() // This should never fail...
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use crate::{
+        path::PathMaybeWithLocale,
+        state::{BuildPaths, StateGeneratorInfo},
+        stores::{MemoryMutableStore, MutableStore},
+        template::Template,
+        Request,
+    };
+    use futures::{executor::block_on, task::noop_waker_ref};
+    use http::StatusCode;
+    use std::{future::Future, task::Context};
+
+    async fn get_build_state(info: StateGeneratorInfo<()>) -> TestState {
+        TestState { path: info.path }
+    }
+    async fn get_build_paths() -> BuildPaths {
+        BuildPaths {
+            paths: vec!["a".to_string(), "b".to_string()],
+            extra: ().into(),
+        }
+    }
+    async fn should_revalidate(_info: StateGeneratorInfo<()>, _req: Request) -> bool {
+        false
+    }
+
+    /// Empties the given store, as if the server had just restarted.
+    async fn clear_store(store: &MemoryMutableStore) {
+        for name in store.list("").await.unwrap() {
+            store.delete(&name).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn should_serve_revalidating_page_evicted_from_memory_store() {
+        let store = MemoryMutableStore::new().max_entries(1);
+        let template = Template::build("page")
+            .view_with_unreactive_state(|cx, state: TestState| {
+                sycamore::view! { cx, p { (state.path) } }
+            })
+            .build_state_fn(get_build_state)
+            .build_paths_fn(get_build_paths)
+            .revalidate_after("1w")
+            .build();
+        let mut turbine = test_turbine(vec![template], store.clone());
+        block_on(async {
+            turbine.build().await.unwrap();
+            // Building `b` will have evicted everything for `a`, but nothing for `b`
+            assert!(!store.exists("static/xx-XX-page%2Fa.json").await.unwrap());
+            assert!(store.exists("static/xx-XX-page%2Fb.json").await.unwrap());
+
+            let res = turbine
+                .get_initial_load(PathMaybeWithLocale("page/a".to_string()), test_request())
+                .await;
+            assert_eq!(res.status, StatusCode::OK);
+            assert!(res.body.contains(r#"{"path":"a"}"#));
+        });
+    }
+    #[test]
+    fn should_regenerate_page_missing_from_memory_store() {
+        let store = MemoryMutableStore::new();
+        let template = Template::build("page")
+            .view_with_unreactive_state(|cx, state: TestState| {
+                sycamore::view! { cx, p { (state.path) } }
+            })
+            .build_state_fn(get_build_state)
+            .build_paths_fn(get_build_paths)
+            .should_revalidate_fn(should_revalidate)
+            .build();
+        let mut turbine = test_turbine(vec![template], store.clone());
+        block_on(async {
+            turbine.build().await.unwrap();
+            clear_store(&store).await;
+
+            let res = turbine
+                .get_initial_load(PathMaybeWithLocale("page/a".to_string()), test_request())
+                .await;
+            assert_eq!(res.status, StatusCode::OK);
+            assert!(res.body.contains(r#"{"path":"a"}"#));
+        });
+    }
+    #[test]
+    fn should_not_defer_revalidation_with_nothing_stale_to_serve() {
+        let store = MemoryMutableStore::new();
+        let template = Template::build("page")
+            .view_with_unreactive_state(|cx, state: TestState| {
+                sycamore::view! { cx, p { (state.path) } }
+            })
+            .build_state_fn(get_build_state)
+            .build_paths_fn(get_build_paths)
+            .revalidate_after("1w")
+            .revalidate_in_background()
+            .build();
+        let mut turbine = test_turbine(vec![template], store.clone());
+        block_on(async {
+            turbine.build().await.unwrap();
+            clear_store(&store).await;
+        });
+
+        // Start processing background tasks, so revalidations could be deferred
+        let mut background = Box::pin(turbine.run_background_tasks());
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(background.as_mut().poll(&mut cx).is_pending());
+
+        let res = block_on(
+            turbine.get_initial_load(PathMaybeWithLocale("page/a".to_string()), test_request()),
+        );
+        assert_eq!(res.status, StatusCode::OK);
+        assert!(res.body.contains(r#"{"path":"a"}"#));
+    }
+}
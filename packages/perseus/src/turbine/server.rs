@@ -1,22 +1,37 @@
+use super::serve::StateAndHead;
 use super::Turbine;
 use crate::{
     error_views::ServerErrorData,
-    errors::{err_to_status_code, ServerError},
-    i18n::{TranslationsManager, Translator},
+    errors::{err_to_redirect, err_to_status_code, ServerError},
+    i18n::{detect_locale_from_header, TranslationsManager, Translator},
     path::{PathMaybeWithLocale, PathWithoutLocale},
-    router::{match_route, FullRouteInfo, FullRouteVerdict},
-    server::get_path_slice,
+    router::{match_route, redirect_stub, FullRouteInfo, FullRouteVerdict, RouteRuleMatch},
+    server::{get_path_slice, HtmlShell},
     state::TemplateState,
     stores::MutableStore,
-    utils::get_path_prefix_server,
+    template::Entity,
+    utils::{get_path_prefix_server, server_timing_header, time_request},
     Request,
 };
 use fmterr::fmt_err;
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    StreamExt,
+};
 use http::{
     header::{self, HeaderName},
     HeaderMap, HeaderValue, StatusCode,
 };
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    future::Future,
+    hash::{Hash, Hasher},
+    time::Instant,
+};
+use sycamore::web::SsrNode;
 
 /// The integration-agnostic representation of the response Perseus will give to
 /// HTTP requests.
@@ -79,6 +94,71 @@ impl ApiResponse {
         );
         self
     }
+    /// Adds an `ETag` HTTP header to this response, computed from its body
+    /// (unless one has already been set), so that clients can cheaply check if
+    /// their cached copy is still up to date. If the given `If-None-Match`
+    /// header from the request shows that it is, this will become a *304 Not
+    /// Modified* response with no body.
+    ///
+    /// This only affects *200 OK* responses. The `ETag`s generated are weak,
+    /// and they will only be consistent across servers running the same
+    /// build of an app.
+    pub fn etag(mut self, if_none_match: Option<&HeaderValue>) -> Self {
+        if self.status != StatusCode::OK {
+            return self;
+        }
+
+        // If the user has set their own `ETag`, we'll respect that
+        let etag = match self.headers.get(header::ETAG) {
+            Some(etag) => etag.to_str().unwrap_or_default().to_string(),
+            None => {
+                let mut hasher = DefaultHasher::new();
+                self.body.hash(&mut hasher);
+                let etag = format!("W/\"{:016x}\"", hasher.finish());
+                // This is guaranteed to be valid
+                self.headers
+                    .insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+                etag
+            }
+        };
+
+        if !etag.is_empty() && if_none_match.is_some_and(|val| etag_matches(val, &etag)) {
+            self.body = String::new();
+            self.status = StatusCode::NOT_MODIFIED;
+        }
+        self
+    }
+}
+
+/// The integration-agnostic representation of a response whose body will be
+/// streamed to the client in chunks, rather than sent all at once. The status
+/// code and headers are known before any of the body is.
+pub struct StreamingApiResponse<'a> {
+    /// The chunks of the response body, in order.
+    pub body: BoxStream<'a, String>,
+    /// The additional headers for the response (see [`ApiResponse`]).
+    pub headers: HeaderMap,
+    /// The HTTP status code of the response.
+    pub status: StatusCode,
+}
+impl<'a> From<ApiResponse> for StreamingApiResponse<'a> {
+    /// Converts a normal response into one whose body is streamed in a single
+    /// chunk.
+    fn from(res: ApiResponse) -> Self {
+        Self {
+            body: stream::once(future::ready(res.body)).boxed(),
+            headers: res.headers,
+            status: res.status,
+        }
+    }
+}
+impl<'a> fmt::Debug for StreamingApiResponse<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingApiResponse")
+            .field("headers", &self.headers)
+            .field("status", &self.status)
+            .finish_non_exhaustive()
+    }
 }
 
 /// The query parameters used in subsequent load requests. This is provided for
@@ -95,6 +175,16 @@ pub struct SubsequentLoadQueryParams {
     pub was_incremental_match: bool,
 }
 
+/// The query parameters used in on-demand revalidation requests.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RevalidationQueryParams {
+    /// The path of the page or widget to revalidate, without the locale.
+    pub path: String,
+    /// The locale to revalidate the page or widget in. If this isn't provided,
+    /// it will be revalidated in every locale the app supports.
+    pub locale: Option<String>,
+}
+
 impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
     /// The endpoint for getting translations.
     ///
@@ -131,6 +221,31 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         entity_name: String,
         was_incremental_match: bool,
         req: Request,
+    ) -> ApiResponse {
+        let (mut response, timing) = self
+            .time_endpoint(
+                "subsequent_load",
+                self.get_subsequent_load_inner(
+                    raw_path,
+                    locale,
+                    entity_name,
+                    was_incremental_match,
+                    req,
+                ),
+            )
+            .await;
+        if let Some(timing) = timing {
+            response.add_header(HeaderName::from_static("server-timing"), timing);
+        }
+        response
+    }
+    async fn get_subsequent_load_inner(
+        &self,
+        raw_path: PathWithoutLocale,
+        locale: String,
+        entity_name: String,
+        was_incremental_match: bool,
+        req: Request,
     ) -> ApiResponse {
         // Check if the locale is supported
         if self.locales.is_supported(&locale) {
@@ -144,11 +259,29 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                 }
             });
 
+            // Make sure the user is allowed to see this page before generating anything for
+            // it (widgets can't have guards)
+            if let Some(entity) = self.entities.get(&entity_name) {
+                match self.check_guard(&path, &locale, entity, &req).await {
+                    Ok(None) => (),
+                    Ok(Some(dest)) => return subsequent_load_redirect(&dest, 307),
+                    Err(err) => {
+                        let status = StatusCode::from_u16(err_to_status_code(&err)).unwrap();
+                        return ApiResponse::err(status, &fmt_err(&err));
+                    }
+                }
+            }
+
+            let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
             let page_data_partial = self
                 .get_state_for_path(path, locale, &entity_name, was_incremental_match, req)
                 .await;
             let page_data_partial = match page_data_partial {
                 Ok(partial) => partial,
+                Err(err) if err_to_redirect(&err).is_some() => {
+                    let (dest, status) = err_to_redirect(&err).unwrap();
+                    return subsequent_load_redirect(dest, status);
+                }
                 Err(err) => {
                     // Parse the error to an appropriate status code
                     let status = StatusCode::from_u16(err_to_status_code(&err)).unwrap();
@@ -159,7 +292,9 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
 
             // We know the form of this, and it should never fail
             let page_data_str = serde_json::to_string(&page_data_partial).unwrap();
-            ApiResponse::ok(&page_data_str).content_type("application/json")
+            ApiResponse::ok(&page_data_str)
+                .content_type("application/json")
+                .etag(if_none_match.as_ref())
         } else {
             ApiResponse::not_found("locale not supported")
         }
@@ -182,6 +317,19 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         &self,
         raw_path: PathMaybeWithLocale,
         req: Request,
+    ) -> ApiResponse {
+        let (mut response, timing) = self
+            .time_endpoint("initial_load", self.get_initial_load_inner(raw_path, req))
+            .await;
+        if let Some(timing) = timing {
+            response.add_header(HeaderName::from_static("server-timing"), timing);
+        }
+        response
+    }
+    async fn get_initial_load_inner(
+        &self,
+        raw_path: PathMaybeWithLocale,
+        req: Request,
     ) -> ApiResponse {
         // Decode the URL so we can work with spaces and special characters
         let raw_path = match urlencoding::decode(&raw_path) {
@@ -194,7 +342,17 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                 )
             }
         };
-        let raw_path = PathMaybeWithLocale(raw_path.as_str().to_string());
+        // Redirects and rewrites take priority over everything else
+        let raw_path = match self
+            .route_rules
+            .resolve(&raw_path, &get_path_prefix_server())
+        {
+            Some(RouteRuleMatch::Redirect { dest, status }) => {
+                return redirect_response(&dest, status)
+            }
+            Some(RouteRuleMatch::Rewrite(path)) => PathMaybeWithLocale(path),
+            None => PathMaybeWithLocale(raw_path.as_str().to_string()),
+        };
 
         // Run the routing algorithm to figure out what to do here
         let path_slice = get_path_slice(&raw_path);
@@ -206,43 +364,28 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                 locale,
                 was_incremental_match,
             }) => {
-                // Get the translations to interpolate into the page
-                let translations_str = self
-                    .translations_manager
-                    .get_translations_str_for_locale(locale.clone())
-                    .await;
-                let translations_str = match translations_str {
-                    Ok(translations) => translations,
-                    // We know for sure that this locale is supported, so there's been an internal
-                    // server error if it can't be found
-                    Err(err) => {
-                        return self.html_err(500, fmt_err(&err), None);
+                let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+                // Pages that can't change at request-time might already be cached
+                let cache_key = format!("{}-{}", locale, path.0);
+                let cache_generation = match &self.initial_load_cache {
+                    Some(cache) if self.initial_load_is_cacheable(entity) => {
+                        if let Some(response) = cache.get(&cache_key) {
+                            return response.etag(if_none_match.as_ref());
+                        }
+                        Some(cache.generation())
                     }
+                    _ => None,
                 };
 
-                // We can use those to get a translator efficiently
-                let translator = match self
-                    .translations_manager
-                    .get_translator_for_translations_str(locale.clone(), translations_str.clone())
-                    .await
-                {
+                let translator = match self.get_initial_load_translator(&locale).await {
                     Ok(translator) => translator,
-                    // We need to give a proper translator to the error pages, which we can't
-                    Err(err) => return self.html_err(500, fmt_err(&err), None),
+                    Err(msg) => return self.html_err(500, msg, None),
                 };
-
-                // This returns both the page data and the most up-to-date global state
-                let res = self
-                    .get_initial_load_for_path(
-                        path,
-                        &translator,
-                        entity,
-                        was_incremental_match,
-                        req,
-                    )
-                    .await;
-                let (page_data, global_state) = match res {
-                    Ok(data) => data,
+                // Make sure the user is allowed to see this page before generating anything for
+                // it
+                match self.check_guard(&path, &locale, entity, &req).await {
+                    Ok(None) => (),
+                    Ok(Some(dest)) => return redirect_response(&dest, 307),
                     Err(err) => {
                         return self.html_err(
                             err_to_status_code(&err),
@@ -250,6 +393,36 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                             Some(&translator),
                         )
                     }
+                }
+
+                // This returns the page data, the most up-to-date global state, and whether or
+                // not the page was prerendered
+                let res = async {
+                    let (page_state, global_state) = self
+                        .get_initial_state_for_path(
+                            path.clone(),
+                            &translator,
+                            entity,
+                            was_incremental_match,
+                            &req,
+                        )
+                        .await?;
+                    let (page_data, prerendered) = self
+                        .render_initial_load_for_path(
+                            path,
+                            &translator,
+                            entity,
+                            page_state,
+                            global_state.clone(),
+                            &req,
+                        )
+                        .await?;
+                    Ok::<_, ServerError>((page_data, global_state, prerendered))
+                }
+                .await;
+                let (page_data, global_state, prerendered) = match res {
+                    Ok(data) => data,
+                    Err(err) => return self.initial_load_err(&err, &translator),
                 };
 
                 let final_html = self
@@ -284,9 +457,36 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                     response.add_header(key.unwrap(), val);
                 }
 
-                response
+                // If any of the page's widgets weren't prerendered, they could change
+                if let (Some(cache), Some(generation), true) =
+                    (&self.initial_load_cache, cache_generation, prerendered)
+                {
+                    cache.insert(cache_key, &response, generation);
+                }
+
+                response.etag(if_none_match.as_ref())
             }
             FullRouteVerdict::LocaleDetection(redirect_path) => {
+                // Work out the user's locale from the `Accept-Language` header, so clients
+                // without Wasm (like crawlers) can be redirected to the right place
+                let locale = match req
+                    .headers()
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|val| val.to_str().ok())
+                {
+                    Some(accept_language) => {
+                        detect_locale_from_header(accept_language, &self.locales)
+                    }
+                    None => self.locales.default.to_string(),
+                };
+                let redirect_url = format!(
+                    "{}/{}/{}",
+                    get_path_prefix_server(),
+                    locale,
+                    // This is a `PathWithoutLocale`
+                    redirect_path.0,
+                );
+
                 // Locale redirection pages still need to have the global state (unlocalized,
                 // see #267), but we obviously won't be able to output a nice
                 // internationalized error if this goes wrong here
@@ -296,62 +496,282 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
                         return self.html_err(err_to_status_code(&err), fmt_err(&err), None)
                     }
                 };
-                // TODO Parse the `Accept-Language` header and return a proper redirect
-                // Construct a locale redirection fallback
+                // Construct a locale redirection fallback, for any clients that don't follow
+                // the `Location` header
                 let html = self
                     .html_shell
                     .as_ref()
                     .unwrap() // We assume the app has been built
                     .clone()
                     .locale_redirection_fallback(
-                        // This is the dumb destination we'd use if Wasm isn't enabled (the default
-                        // locale). It has *zero* bearing on what the Wasm
-                        // bundle will do.
-                        &format!(
-                            "{}/{}/{}",
-                            get_path_prefix_server(),
-                            &self.locales.default,
-                            // This is a `PathWithoutLocale`
-                            redirect_path.0,
-                        ),
+                        // This is the dumb destination we'd use if Wasm isn't enabled. It has
+                        // *zero* bearing on what the Wasm bundle will do.
+                        &redirect_url,
                         &global_state,
                     )
                     .to_string();
-                // TODO Headers? They weren't here in the old code...
                 // This isn't an error, but that's how this API expresses it (302 redirect)
-                ApiResponse::err(StatusCode::FOUND, &html).content_type("text/html")
+                let mut response =
+                    ApiResponse::err(StatusCode::FOUND, &html).content_type("text/html");
+                // Decoded paths might contain characters that can't go in a header, in which
+                // case we'll have to rely on the fallback
+                if let Ok(location) = HeaderValue::from_str(&redirect_url) {
+                    response.add_header(header::LOCATION, location);
+                }
+                // Caches must not give this redirect to users who prefer other languages
+                response.add_header(header::VARY, HeaderValue::from_static("Accept-Language"));
+                response
             }
             // Any unlocalized 404s would go to a redirect first
             FullRouteVerdict::NotFound { locale } => {
-                // Get the translations to interpolate into the page
-                let translations_str = self
-                    .translations_manager
-                    .get_translations_str_for_locale(locale.clone())
-                    .await;
-                let translations_str = match translations_str {
-                    Ok(translations) => translations,
-                    // We know for sure that this locale is supported, so there's been an internal
-                    // server error if it can't be found
-                    Err(err) => {
-                        return self.html_err(500, fmt_err(&err), None);
-                    }
-                };
-
-                // We can use those to get a translator efficiently
-                let translator = match self
-                    .translations_manager
-                    .get_translator_for_translations_str(locale, translations_str.clone())
-                    .await
-                {
+                let translator = match self.get_initial_load_translator(&locale).await {
                     Ok(translator) => translator,
-                    // We need to give a proper translator to the error pages, which we can't
-                    Err(err) => return self.html_err(500, fmt_err(&err), None),
+                    Err(msg) => return self.html_err(500, msg, None),
                 };
 
                 self.html_err(404, "page not found".to_string(), Some(&translator))
             }
         }
     }
+    /// A streaming version of `.get_initial_load()`, which sends the start of
+    /// the HTML shell's `<head>` (including preloads of the app's bundles) as
+    /// soon as the page's state has been generated, so that the browser can
+    /// start fetching those while the page is being rendered. The rest of the
+    /// document will be sent once rendering is complete, including resolving
+    /// any widgets.
+    ///
+    /// The status code and headers are determined before anything is sent,
+    /// so, if rendering fails after the state has been generated, the error
+    /// page will be sent with the status code that had already been chosen.
+    /// Errors before then, and responses for anything other than a page that
+    /// exists, will be sent exactly as `.get_initial_load()` would send them,
+    /// in a single chunk.
+    ///
+    /// Unlike `.get_initial_load()`, the HTML sent by this will not be
+    /// minified, and it won't have an `ETag` (so `If-None-Match` headers will
+    /// be ignored). Streamed pages also won't be added to the in-memory cache
+    /// of initial loads, if the app has one, although pages already in it
+    /// will be served from it. If the app has enabled server timing, only the
+    /// phases completed before the headers were sent will be included in the
+    /// `Server-Timing` header.
+    pub async fn get_initial_load_stream(
+        &self,
+        raw_path: PathMaybeWithLocale,
+        req: Request,
+    ) -> StreamingApiResponse<'_> {
+        let (mut response, timing) = self
+            .time_endpoint(
+                "initial_load",
+                self.get_initial_load_stream_inner(raw_path, req),
+            )
+            .await;
+        if let Some(timing) = timing {
+            response
+                .headers
+                .insert(HeaderName::from_static("server-timing"), timing);
+        }
+        response
+    }
+    async fn get_initial_load_stream_inner(
+        &self,
+        raw_path: PathMaybeWithLocale,
+        req: Request,
+    ) -> StreamingApiResponse<'_> {
+        // Decode the URL so we can work with spaces and special characters
+        let path = match urlencoding::decode(&raw_path) {
+            Ok(path) => PathMaybeWithLocale(path.to_string()),
+            Err(err) => {
+                return self
+                    .html_err(
+                        400,
+                        fmt_err(&ServerError::UrlDecodeFailed { source: err }),
+                        None,
+                    )
+                    .into()
+            }
+        };
+
+        let path = match self.route_rules.resolve(&path, &get_path_prefix_server()) {
+            Some(RouteRuleMatch::Redirect { dest, status }) => {
+                return redirect_response(&dest, status).into()
+            }
+            Some(RouteRuleMatch::Rewrite(path)) => PathMaybeWithLocale(path),
+            None => path,
+        };
+
+        // Only pages that actually exist benefit from streaming, so everything else
+        // will be handled normally
+        let path_slice = get_path_slice(&path);
+        let verdict = match_route(&path_slice, &self.render_cfg, &self.entities, &self.locales);
+        let FullRouteInfo {
+            path,
+            entity,
+            locale,
+            was_incremental_match,
+        } = match verdict.into_full(&self.entities) {
+            FullRouteVerdict::Found(route_info) => route_info,
+            _ => return self.get_initial_load_inner(raw_path, req).await.into(),
+        };
+        // Cached pages are already complete, so there's nothing to stream
+        if let Some(cache) = &self.initial_load_cache {
+            if self.initial_load_is_cacheable(entity) {
+                if let Some(response) = cache.get(&format!("{}-{}", locale, path.0)) {
+                    return response.into();
+                }
+            }
+        }
+
+        let translator = match self.get_initial_load_translator(&locale).await {
+            Ok(translator) => translator,
+            Err(msg) => return self.html_err(500, msg, None).into(),
+        };
+        match self.check_guard(&path, &locale, entity, &req).await {
+            Ok(None) => (),
+            Ok(Some(dest)) => return redirect_response(&dest, 307).into(),
+            Err(err) => {
+                return self
+                    .html_err(err_to_status_code(&err), fmt_err(&err), Some(&translator))
+                    .into()
+            }
+        }
+        let res = self
+            .get_initial_state_for_path(
+                path.clone(),
+                &translator,
+                entity,
+                was_incremental_match,
+                &req,
+            )
+            .await;
+        let (page_state, global_state) = match res {
+            Ok(data) => data,
+            Err(err) => return self.initial_load_err(&err, &translator).into(),
+        };
+        // The headers can be generated from the state alone, so we don't need to wait
+        // for the render
+        let headers = match entity.get_headers(
+            page_state.state.clone(),
+            global_state.clone(),
+            Some(&translator),
+        ) {
+            Ok(headers) => headers,
+            Err(err) => {
+                return self
+                    .html_err(err_to_status_code(&err), fmt_err(&err), Some(&translator))
+                    .into()
+            }
+        };
+
+        let mut html_shell = self.html_shell.as_ref().unwrap().clone().preload_bundle();
+        // This has to be set now so the `lang` attribute is included in the first
+        // chunk
+        html_shell.locale = locale.clone();
+        let head_start = html_shell.head_start();
+        let rest = self.render_initial_load_rest(
+            path,
+            translator,
+            entity,
+            page_state,
+            global_state,
+            req,
+            html_shell,
+        );
+
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        for (key, val) in headers {
+            response_headers.insert(key.unwrap(), val);
+        }
+        StreamingApiResponse {
+            body: stream::once(future::ready(head_start))
+                .chain(stream::once(rest))
+                .boxed(),
+            headers: response_headers,
+            status: StatusCode::OK,
+        }
+    }
+    /// Renders the given page and interpolates it into the given HTML shell,
+    /// returning everything after the start of the shell's head (which will
+    /// already have been sent to the client). If rendering fails, this will
+    /// instead return the rest of an error page.
+    #[allow(clippy::too_many_arguments)]
+    async fn render_initial_load_rest(
+        &self,
+        path: PathWithoutLocale,
+        translator: Translator,
+        entity: &Entity<SsrNode>,
+        page_state: StateAndHead,
+        global_state: TemplateState,
+        req: Request,
+        html_shell: HtmlShell,
+    ) -> String {
+        let locale = translator.get_locale();
+        let res = self
+            .render_initial_load_for_path(
+                path,
+                &translator,
+                entity,
+                page_state,
+                global_state.clone(),
+                &req,
+            )
+            .await;
+        match res {
+            Ok((page_data, _)) => html_shell
+                .page_data(&page_data, &global_state, &locale)
+                .rest(),
+            Err(err) => {
+                let err_data = ServerErrorData {
+                    status: err_to_status_code(&err),
+                    msg: fmt_err(&err),
+                };
+                self.error_page_shell(html_shell, err_data, Some(&translator))
+                    .rest()
+            }
+        }
+    }
+    /// The endpoint for revalidating a page or widget on-demand (see
+    /// `.revalidate()`). Requests to this must have an `Authorization` header
+    /// of the form `Bearer <secret>`, where the secret is the one given here
+    /// (which integrations take from their `ServerOptions`). Integrations
+    /// should not expose this endpoint at all if no secret has been set.
+    ///
+    /// Responses from this endpoint have the MIME type `text/plain`.
+    pub async fn post_revalidate(
+        &self,
+        params: RevalidationQueryParams,
+        secret: &str,
+        req: Request,
+    ) -> ApiResponse {
+        let provided_secret = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.strip_prefix("Bearer "));
+        match provided_secret {
+            Some(provided_secret) if secrets_match(provided_secret, secret) => (),
+            _ => {
+                return ApiResponse::err(StatusCode::UNAUTHORIZED, "invalid revalidation secret")
+                    .content_type("text/plain")
+            }
+        };
+
+        let locales = match params.locale {
+            Some(locale) => vec![locale],
+            None => self.locales.get_all().into_iter().cloned().collect(),
+        };
+        for locale in locales {
+            if let Err(err) = self
+                .revalidate(PathWithoutLocale(params.path.clone()), &locale)
+                .await
+            {
+                let status = StatusCode::from_u16(err_to_status_code(&err)).unwrap();
+                return ApiResponse::err(status, &fmt_err(&err)).content_type("text/plain");
+            }
+        }
+
+        ApiResponse::ok("revalidated").content_type("text/plain")
+    }
     /// Provides the JS file of initial constants.
     pub async fn get_initial_consts(&self, locale: &str) -> ApiResponse {
         let js_file = match self.initial_consts_js(locale).await {
@@ -362,6 +782,79 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         ApiResponse::ok(&js_file).content_type("text/javascript")
     }
 
+    /// The endpoint for liveness probes, which will succeed as long as the
+    /// server can respond at all.
+    pub fn get_liveness(&self) -> ApiResponse {
+        ApiResponse::ok("OK").content_type("text/plain")
+    }
+    /// The endpoint for readiness probes, which returns the health of the
+    /// server (see `.health()`) as JSON. If the server isn't ready, this
+    /// will have the status *503 Service Unavailable*.
+    pub async fn get_readiness(&self) -> ApiResponse {
+        let health = self.health().await;
+        // We know the form of this, and it should never fail
+        let health_str = serde_json::to_string(&health).unwrap();
+        if health.ready {
+            ApiResponse::ok(&health_str)
+        } else {
+            ApiResponse::err(StatusCode::SERVICE_UNAVAILABLE, &health_str)
+        }
+        .content_type("application/json")
+    }
+
+    /// Gets a translator for the given locale for an initial load. If this
+    /// fails, the error will be returned as a message for an HTML error page
+    /// (it will always be an internal server error).
+    async fn get_initial_load_translator(&self, locale: &str) -> Result<Translator, String> {
+        // Get the translations to interpolate into the page
+        let translations_str = self
+            .translations_manager
+            .get_translations_str_for_locale(locale.to_string())
+            .await;
+        let translations_str = match translations_str {
+            Ok(translations) => translations,
+            // We know for sure that this locale is supported, so there's been an internal
+            // server error if it can't be found
+            Err(err) => return Err(fmt_err(&err)),
+        };
+
+        // We can use those to get a translator efficiently
+        match self
+            .translations_manager
+            .get_translator_for_translations_str(locale.to_string(), translations_str)
+            .await
+        {
+            Ok(translator) => Ok(translator),
+            // We need to give a proper translator to the error pages, which we can't
+            Err(err) => Err(fmt_err(&err)),
+        }
+    }
+
+    /// Runs the given endpoint handler, returning a `Server-Timing` header
+    /// describing how long each phase of it took, if the app has enabled
+    /// server timing.
+    async fn time_endpoint<F: Future>(
+        &self,
+        endpoint: &'static str,
+        handler: F,
+    ) -> (F::Output, Option<HeaderValue>) {
+        if !self.server_timing {
+            return (handler.await, None);
+        }
+
+        let start = Instant::now();
+        let (output, phases) = time_request(endpoint, handler).await;
+        (output, Some(server_timing_header(&phases, start.elapsed())))
+    }
+
+    /// Creates a response for an error that occurred while generating the
+    /// state of a page for an initial load, which may actually be a redirect.
+    fn initial_load_err(&self, err: &ServerError, translator: &Translator) -> ApiResponse {
+        match err_to_redirect(err) {
+            Some((dest, status)) => redirect_response(dest, status),
+            None => self.html_err(err_to_status_code(err), fmt_err(err), Some(translator)),
+        }
+    }
     // TODO If we ever support error headers, this would be the place to do it; PRs
     // welcome!
     /// Creates an HTML error page for when the initial load handler needs one.
@@ -377,3 +870,84 @@ impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
         ApiResponse::err(StatusCode::from_u16(status).unwrap(), &html).content_type("text/html")
     }
 }
+
+/// Creates a response that redirects to the given URL with the given status
+/// code. This also has a body that will redirect clients that don't follow the
+/// `Location` header.
+fn redirect_response(dest: &str, status: u16) -> ApiResponse {
+    // This is always a valid redirection status code
+    let status = StatusCode::from_u16(status).unwrap();
+    let mut response = ApiResponse::err(status, &redirect_stub(dest)).content_type("text/html");
+    // Destinations with characters that can't go in a header will have to rely on
+    // the body
+    if let Ok(location) = HeaderValue::from_str(dest) {
+        response.add_header(header::LOCATION, location);
+    }
+    response
+}
+
+/// Creates a response to a subsequent load that redirects to the given URL.
+/// The browser can't follow real redirects for these, since it would end up
+/// fetching HTML, so this sends the destination as the body, without a
+/// `Location` header, and the app will navigate there itself.
+fn subsequent_load_redirect(dest: &str, status: u16) -> ApiResponse {
+    // This is always a valid redirection status code
+    ApiResponse::err(StatusCode::from_u16(status).unwrap(), dest)
+}
+
+/// Checks whether or not the given `If-None-Match` header matches the given
+/// `ETag`. This uses weak comparison, as required for `If-None-Match`.
+fn etag_matches(if_none_match: &HeaderValue, etag: &str) -> bool {
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    match if_none_match.to_str() {
+        Ok(if_none_match) => if_none_match.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        }),
+        Err(_) => false,
+    }
+}
+
+/// Compares the given secrets in constant time (with respect to their
+/// contents), so that response times don't leak how much of a guessed secret
+/// was correct.
+fn secrets_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etag_should_match_weakly() {
+        let etag = "W/\"abc\"";
+        assert!(etag_matches(&HeaderValue::from_static("W/\"abc\""), etag));
+        assert!(etag_matches(&HeaderValue::from_static("\"abc\""), etag));
+        assert!(etag_matches(
+            &HeaderValue::from_static("\"xyz\", W/\"abc\""),
+            etag
+        ));
+        assert!(etag_matches(&HeaderValue::from_static("*"), etag));
+        assert!(!etag_matches(&HeaderValue::from_static("W/\"xyz\""), etag));
+    }
+
+    #[test]
+    fn matching_etag_should_give_not_modified() {
+        let res = ApiResponse::ok("body").etag(None);
+        assert_eq!(res.status, StatusCode::OK);
+        let etag = res.headers.get(header::ETAG).unwrap().clone();
+
+        let res = ApiResponse::ok("body").etag(Some(&etag));
+        assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+        assert!(res.body.is_empty());
+        assert_eq!(res.headers.get(header::ETAG), Some(&etag));
+
+        let res = ApiResponse::ok("other body").etag(Some(&etag));
+        assert_eq!(res.status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,232 @@
+use super::Turbine;
+use crate::{
+    errors::ServerError, i18n::TranslationsManager, path::PurePath, state::TemplateState,
+    stores::MutableStore, template::Entity,
+};
+use futures::{
+    channel::oneshot,
+    future::{FutureExt, Shared},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use sycamore::web::SsrNode;
+
+/// The entity name, path, and locale of a page/widget being generated.
+type FlightKey = (String, String, String);
+/// A handle to the result of a generation that's in progress. If the
+/// generation is abandoned (e.g. because the request that was running it was
+/// cancelled), this will resolve to an error.
+type Flight = Shared<oneshot::Receiver<Result<(), Arc<ServerError>>>>;
+
+/// A map of the request-time generations that are currently in progress, which
+/// lets concurrent requests for the same page/widget share a single
+/// generation, rather than all running the user's state generation functions
+/// (and writing to the mutable store) separately.
+#[derive(Debug, Default)]
+pub(super) struct InFlightMap(Mutex<HashMap<FlightKey, Flight>>);
+
+/// Removes a generation from the in-flight map when dropped, so that an
+/// abandoned generation doesn't block all future ones.
+struct FlightGuard<'a> {
+    map: &'a InFlightMap,
+    key: &'a FlightKey,
+}
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        self.map
+            .0
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(self.key);
+    }
+}
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Builds the given page/widget at request-time, as for incremental
+    /// generation or revalidation. If the same page/widget is already being
+    /// built in the same locale, this will wait for that to finish and share
+    /// its result, rather than building it again.
+    ///
+    /// This only coalesces what gets written to the mutable store: request
+    /// state is specific to each request, so it's never shared.
+    pub(super) async fn build_path_or_widget_once(
+        &self,
+        path: PurePath,
+        entity: &Entity<SsrNode>,
+        extra: &TemplateState,
+        locale: &str,
+        global_state: TemplateState,
+        force_mutable: bool,
+    ) -> Result<(), ServerError> {
+        let key = (entity.get_path(), path.0.clone(), locale.to_string());
+
+        // Work out whether or not we're the ones who have to do the building (we loop
+        // here in case the request we were waiting for is cancelled)
+        let sender = loop {
+            let flight = {
+                let mut in_flight = self
+                    .in_flight
+                    .0
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner());
+                match in_flight.get(&key) {
+                    Some(flight) => flight.clone(),
+                    None => {
+                        let (sender, receiver) = oneshot::channel();
+                        in_flight.insert(key.clone(), receiver.shared());
+                        break sender;
+                    }
+                }
+            };
+            match flight.await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(err)) => return Err(ServerError::Shared(err)),
+                // Whoever was building this gave up, so we'll take over
+                Err(oneshot::Canceled) => continue,
+            }
+        };
+
+        let guard = FlightGuard {
+            map: &self.in_flight,
+            key: &key,
+        };
+        let res = self
+            .build_path_or_widget_for_locale(
+                path.clone(),
+                entity,
+                extra,
+                locale,
+                global_state,
+                false,
+                force_mutable,
+            )
+            .await;
+        // Anyone who comes along from now on should see the new version in the store
+        drop(guard);
+        self.invalidate_initial_load(&path, entity, locale);
+
+        match res {
+            Ok(_) => {
+                let _ = sender.send(Ok(()));
+                Ok(())
+            }
+            Err(err) => {
+                let err = Arc::new(err);
+                let _ = sender.send(Err(err.clone()));
+                // If no-one else was waiting on this, we can get the original back
+                Err(Arc::try_unwrap(err).unwrap_or_else(ServerError::Shared))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use crate::{
+        path::PurePath,
+        state::{StateGeneratorInfo, TemplateState},
+        stores::MemoryMutableStore,
+        template::Template,
+    };
+    use futures::{executor::block_on, future::poll_fn, task::noop_waker_ref};
+    use std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+    use sycamore::web::SsrNode;
+
+    /// Creates a template whose state generation yields once (so that other
+    /// requests get a chance to come in while it's in progress), counting how
+    /// many times it's been run.
+    fn counting_template(builds: Arc<AtomicUsize>) -> Template<SsrNode> {
+        Template::build("page")
+            .view_with_unreactive_state(|cx, state: TestState| {
+                sycamore::view! { cx, p { (state.path) } }
+            })
+            .build_state_fn(move |info: StateGeneratorInfo<()>| {
+                builds.fetch_add(1, Ordering::SeqCst);
+                let mut yielded = false;
+                async move {
+                    poll_fn(|cx| {
+                        if yielded {
+                            Poll::Ready(())
+                        } else {
+                            yielded = true;
+                            cx.waker().wake_by_ref();
+                            Poll::Pending
+                        }
+                    })
+                    .await;
+                    TestState { path: info.path }
+                }
+            })
+            .build()
+    }
+
+    #[test]
+    fn concurrent_builds_should_be_coalesced() {
+        let builds = Arc::new(AtomicUsize::new(0));
+        let turbine = test_turbine(
+            vec![counting_template(builds.clone())],
+            MemoryMutableStore::new(),
+        );
+        let entity = turbine.entities.get("page").unwrap();
+        let extra = TemplateState::empty();
+        let build = || {
+            turbine.build_path_or_widget_once(
+                PurePath(String::new()),
+                entity,
+                &extra,
+                "xx-XX",
+                TemplateState::empty(),
+                true,
+            )
+        };
+
+        let (first, second) = block_on(async { futures::join!(build(), build()) });
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn cancelled_build_should_be_taken_over() {
+        let builds = Arc::new(AtomicUsize::new(0));
+        let turbine = test_turbine(
+            vec![counting_template(builds.clone())],
+            MemoryMutableStore::new(),
+        );
+        let entity = turbine.entities.get("page").unwrap();
+        let extra = TemplateState::empty();
+        let build = || {
+            Box::pin(turbine.build_path_or_widget_once(
+                PurePath(String::new()),
+                entity,
+                &extra,
+                "xx-XX",
+                TemplateState::empty(),
+                true,
+            ))
+        };
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // The first request starts building, and the second waits for it
+        let mut leader = build();
+        assert!(leader.as_mut().poll(&mut cx).is_pending());
+        let mut waiter = build();
+        assert!(waiter.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        // If the first request is cancelled, the second should build it itself
+        drop(leader);
+        block_on(waiter).unwrap();
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,231 @@
+use super::Turbine;
+use crate::{errors::*, i18n::TranslationsManager, stores::MutableStore};
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+/// The name of the asset in the mutable store that records that something in
+/// the app could have been invalidated.
+const INVALIDATIONS_POSSIBLE_MARKER: &str = "tags/invalidations_possible.txt";
+/// How often we check the mutable store for whether or not another process
+/// has invalidated something, until we know that something could have been.
+const INVALIDATIONS_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+impl<M: MutableStore, T: TranslationsManager> Turbine<M, T> {
+    /// Marks every page and widget whose state was generated with the given
+    /// cache tag (see [`StateGeneratorInfo::add_cache_tag`]) as needing
+    /// revalidation. They won't be regenerated immediately, but the next
+    /// request for each of them will regenerate it (or, if its template
+    /// revalidates in the background, queue that regeneration).
+    ///
+    /// Each page or widget will attach its tags again when it's regenerated,
+    /// so the tag will keep working for future invalidations.
+    ///
+    /// [`StateGeneratorInfo::add_cache_tag`]: crate::state::StateGeneratorInfo::add_cache_tag
+    pub async fn invalidate_tag(&self, tag: &str) -> Result<(), ServerError> {
+        let _guard = self.tag_index_lock.lock().await;
+
+        let paths = self.read_tag_index(tag).await?;
+        if !paths.is_empty() {
+            self.note_invalidations_possible().await?;
+        }
+        for path_encoded in paths.iter() {
+            self.mutable_store
+                .write(&invalidation_marker(path_encoded), "true")
+                .await?;
+        }
+        // Every one of these will be re-added when it's regenerated
+        if !paths.is_empty() {
+            self.mutable_store.write(&tag_index(tag), "[]").await?;
+        }
+
+        Ok(())
+    }
+
+    /// Records that the state of the given page/widget (as a locale and
+    /// encoded path) was generated with the given cache tags.
+    pub(super) async fn record_cache_tags(
+        &self,
+        path_encoded: &str,
+        tags: Vec<String>,
+    ) -> Result<(), ServerError> {
+        if tags.is_empty() {
+            return Ok(());
+        }
+        self.note_invalidations_possible().await?;
+        // Many pages could be adding themselves to the same tag at once
+        let _guard = self.tag_index_lock.lock().await;
+
+        for tag in tags {
+            let mut paths = self.read_tag_index(&tag).await?;
+            if !paths.iter().any(|path| path == path_encoded) {
+                paths.push(path_encoded.to_string());
+                let paths_str = serde_json::to_string(&paths).unwrap();
+                self.mutable_store
+                    .write(&tag_index(&tag), &paths_str)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the given page/widget has been invalidated through a cache
+    /// tag. If nothing in the app could have been invalidated, this will only
+    /// touch the mutable store every few seconds, to pick up invalidations
+    /// made by other processes sharing it.
+    pub(super) async fn is_invalidated(&self, path_encoded: &str) -> Result<bool, ServerError> {
+        if !self.invalidations_possible.load(Ordering::Acquire)
+            && !self.recheck_invalidations_possible().await?
+        {
+            return Ok(false);
+        }
+        match self
+            .mutable_store
+            .read(&invalidation_marker(path_encoded))
+            .await
+        {
+            // We empty the marker if we can't delete it
+            Ok(marker) => Ok(!marker.is_empty()),
+            Err(StoreError::NotFound { .. }) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Sets whether or not the given page/widget is invalidated.
+    pub(super) async fn set_invalidated(
+        &self,
+        path_encoded: &str,
+        invalidated: bool,
+    ) -> Result<(), ServerError> {
+        let marker = invalidation_marker(path_encoded);
+        if invalidated {
+            self.note_invalidations_possible().await?;
+            self.mutable_store.write(&marker, "true").await?;
+        } else {
+            match self.mutable_store.delete(&marker).await {
+                Ok(_) => (),
+                Err(StoreError::NotSupported { .. }) => {
+                    self.mutable_store.write(&marker, "").await?
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records that something in the app could be marked as invalidated from
+    /// now on. This is persisted, so that other processes sharing the mutable
+    /// store (including this one after a restart) will pick up invalidations
+    /// made by this one.
+    async fn note_invalidations_possible(&self) -> Result<(), ServerError> {
+        if !self.invalidations_possible.swap(true, Ordering::AcqRel) {
+            self.mutable_store
+                .write(INVALIDATIONS_POSSIBLE_MARKER, "true")
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads whether or not anything in the app could have been invalidated
+    /// from the mutable store.
+    pub(super) async fn load_invalidations_possible(&mut self) -> Result<(), ServerError> {
+        let possible = self.read_invalidations_possible().await?;
+        // We don't want to forget anything we've noted in this process
+        if possible {
+            *self.invalidations_possible.get_mut() = true;
+        }
+        *self.invalidations_checked_at.get_mut().unwrap() = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Checks the mutable store again for whether or not anything in the app
+    /// could have been invalidated (e.g. by another process sharing the
+    /// store), if we haven't done so recently. This returns whether or not
+    /// something could have been invalidated, which will be `false` if we
+    /// didn't check.
+    async fn recheck_invalidations_possible(&self) -> Result<bool, ServerError> {
+        {
+            let mut checked_at = self.invalidations_checked_at.lock().unwrap();
+            if matches!(*checked_at, Some(at) if at.elapsed() < INVALIDATIONS_RECHECK_INTERVAL) {
+                return Ok(false);
+            }
+            *checked_at = Some(Instant::now());
+        }
+
+        let possible = self.read_invalidations_possible().await?;
+        if possible {
+            self.invalidations_possible.store(true, Ordering::Release);
+        }
+        Ok(possible)
+    }
+
+    /// Reads whether or not anything in the app could have been invalidated
+    /// from the mutable store.
+    async fn read_invalidations_possible(&self) -> Result<bool, ServerError> {
+        match self.mutable_store.read(INVALIDATIONS_POSSIBLE_MARKER).await {
+            Ok(marker) => Ok(!marker.is_empty()),
+            Err(StoreError::NotFound { .. }) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Reads the list of pages/widgets (as locales and encoded paths) that
+    /// depend on the given tag.
+    async fn read_tag_index(&self, tag: &str) -> Result<Vec<String>, ServerError> {
+        match self.mutable_store.read(&tag_index(tag)).await {
+            Ok(paths_str) => {
+                serde_json::from_str(&paths_str).map_err(|err| ServerError::InvalidTagIndex {
+                    tag: tag.to_string(),
+                    source: err,
+                })
+            }
+            Err(StoreError::NotFound { .. }) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Gets the name of the asset in the mutable store that lists everything that
+/// depends on the given tag.
+fn tag_index(tag: &str) -> String {
+    format!("tags/{}.json", urlencoding::encode(tag))
+}
+/// Gets the name of the asset in the mutable store that marks the given
+/// page/widget as having been invalidated.
+fn invalidation_marker(path_encoded: &str) -> String {
+    format!("static/{}.invalidated.txt", path_encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+    use crate::stores::MemoryMutableStore;
+    use futures::executor::block_on;
+
+    #[test]
+    fn should_notice_invalidations_from_other_processes() {
+        let store = MemoryMutableStore::new();
+        let mut turbine = test_turbine(Vec::new(), store.clone());
+        block_on(turbine.load_invalidations_possible()).unwrap();
+
+        // Another process sharing the store invalidates a page after we've
+        // started
+        let mut other = test_turbine(Vec::new(), store);
+        block_on(other.load_invalidations_possible()).unwrap();
+        block_on(other.set_invalidated("xx-XX-a", true)).unwrap();
+
+        // We only just checked, so we won't look at the store again yet
+        assert!(!block_on(turbine.is_invalidated("xx-XX-a")).unwrap());
+        // But we will once the interval has passed
+        *turbine.invalidations_checked_at.get_mut().unwrap() =
+            Some(Instant::now() - INVALIDATIONS_RECHECK_INTERVAL);
+        assert!(block_on(turbine.is_invalidated("xx-XX-a")).unwrap());
+        assert!(!block_on(turbine.is_invalidated("xx-XX-b")).unwrap());
+    }
+}
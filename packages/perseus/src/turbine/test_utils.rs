@@ -0,0 +1,56 @@
+//! Utilities for testing the turbine with apps that live entirely in memory.
+
+use super::Turbine;
+use crate::{
+    errors::StoreError,
+    i18n::FsTranslationsManager,
+    init::PerseusAppBase,
+    state::UnreactiveState,
+    stores::{ImmutableStore, MemoryMutableStore, MutableStore},
+    template::Template,
+    Request,
+};
+use serde::{Deserialize, Serialize};
+use sycamore::web::SsrNode;
+
+/// A simple state for test pages.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(super) struct TestState {
+    pub(super) path: String,
+}
+impl UnreactiveState for TestState {}
+
+/// An immutable store that keeps everything in memory, so tests don't touch
+/// the filesystem.
+#[derive(Debug, Default)]
+struct MemoryImmutableStore(MemoryMutableStore);
+#[async_trait::async_trait]
+impl ImmutableStore for MemoryImmutableStore {
+    async fn read_bytes(&self, name: &str) -> Result<Vec<u8>, StoreError> {
+        self.0.read_bytes(name).await
+    }
+    async fn write_bytes(&self, name: &str, content: &[u8]) -> Result<(), StoreError> {
+        self.0.write_bytes(name, content).await
+    }
+    fn get_path(&self) -> &str {
+        ""
+    }
+}
+
+/// Creates a turbine for an app with the given templates and mutable store,
+/// without i18n. This will need to be built before it can serve anything.
+pub(super) fn test_turbine(
+    templates: Vec<Template<SsrNode>>,
+    mutable_store: MemoryMutableStore,
+) -> Turbine<MemoryMutableStore, FsTranslationsManager> {
+    let app = PerseusAppBase::new_with_mutable_store(mutable_store)
+        .templates(templates)
+        .disable_i18n()
+        .immutable_store(MemoryImmutableStore::default());
+    Turbine::try_from(app).unwrap()
+}
+
+/// Creates an empty request.
+pub(super) fn test_request() -> Request {
+    Request::builder().body(()).unwrap()
+}
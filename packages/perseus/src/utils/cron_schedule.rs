@@ -0,0 +1,169 @@
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A schedule for revalidation defined by a cron expression, which can compute
+/// the next wall-clock time at which something should be revalidated.
+#[derive(Debug, Clone)]
+pub struct CronSchedule(cron::Schedule);
+impl CronSchedule {
+    /// Parses the given cron expression. This can either be a standard
+    /// five-field expression (minute, hour, day of the month, month, day of
+    /// the week), or a six- or seven-field expression with seconds at the
+    /// start (and optionally years at the end).
+    ///
+    /// In five-field expressions, numeric days of the week are standard, going
+    /// from 0 (Sunday) to 6 (Saturday), with 7 also meaning Sunday. In six-
+    /// and seven-field expressions, they go from 1 (Sunday) to 7 (Saturday).
+    /// Either way, it's clearer to use names like `Mon-Fri`.
+    pub fn parse(expr: &str) -> Result<Self, InvalidCronExpression> {
+        let expr = expr.trim();
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        // The parser always expects seconds, and numbers days of the week from 1
+        let full_expr = if fields.len() == 5 {
+            let day_of_week = translate_day_of_week(fields[4]).ok_or_else(|| {
+                InvalidCronExpression::InvalidDayOfWeek {
+                    expr: expr.to_string(),
+                    field: fields[4].to_string(),
+                }
+            })?;
+            format!("0 {} {}", fields[..4].join(" "), day_of_week)
+        } else {
+            expr.to_string()
+        };
+        let schedule = cron::Schedule::from_str(&full_expr).map_err(|err| {
+            InvalidCronExpression::ParseFailed {
+                expr: expr.to_string(),
+                source: err,
+            }
+        })?;
+        Ok(Self(schedule))
+    }
+    /// Gets the timestamp of the next time in this schedule after the given
+    /// time, if there is one.
+    pub fn next_after(&self, time: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.0.after(time).next()
+    }
+    /// Gets the timestamp (RFC 3339) of the next time in this schedule after
+    /// the current time, if there is one.
+    pub fn compute_timestamp(&self) -> Option<String> {
+        self.next_after(&Utc::now()).map(|time| time.to_rfc3339())
+    }
+}
+
+/// Translates the day of the week field of a standard cron expression (where
+/// Sunday is 0 or 7) into the numbering the parser uses (where Sunday is 1),
+/// by listing out the days of any numeric items. Items that use names are left
+/// alone. This returns `None` if a numeric item is invalid.
+fn translate_day_of_week(field: &str) -> Option<String> {
+    let mut items = Vec::new();
+    for item in field.split(',') {
+        if item == "*" || item == "?" || item.chars().any(|c| c.is_ascii_alphabetic()) {
+            items.push(item.to_string());
+            continue;
+        }
+
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|step| *step > 0)?),
+            None => (item, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.parse::<u8>().ok()?, end.parse::<u8>().ok()?),
+            None if range == "*" => (0, 6),
+            // Something like `1/2` means every other day from Monday
+            None if item.contains('/') => (range.parse::<u8>().ok()?, 6),
+            None => {
+                let day = range.parse::<u8>().ok()?;
+                (day, day)
+            }
+        };
+        if end > 7 || start > end {
+            return None;
+        }
+        for day in (start..=end).step_by(step) {
+            let day = (day % 7 + 1).to_string();
+            if !items.contains(&day) {
+                items.push(day);
+            }
+        }
+    }
+
+    Some(items.join(","))
+}
+
+/// An error type for invalid cron expressions.
+#[derive(Error, Debug)]
+pub enum InvalidCronExpression {
+    /// A numeric day of the week in a five-field expression was invalid.
+    #[error("invalid day of the week '{field}' in cron expression '{expr}' (numeric days of the week go from 0 to 7, where both 0 and 7 are Sunday)")]
+    InvalidDayOfWeek {
+        /// The whole expression.
+        expr: String,
+        /// The day of the week field.
+        field: String,
+    },
+    /// The expression couldn't be parsed.
+    #[error("couldn't parse cron expression '{expr}'")]
+    ParseFailed {
+        /// The whole expression.
+        expr: String,
+        /// The underlying parser error.
+        #[source]
+        source: cron::error::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn cron_schedule_accepts_five_fields() {
+        let schedule = CronSchedule::parse("0 6 * * *").unwrap();
+        let time = Utc.with_ymd_and_hms(2023, 1, 1, 7, 30, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(&time),
+            Some(Utc.with_ymd_and_hms(2023, 1, 2, 6, 0, 0).unwrap())
+        );
+
+        let hourly = CronSchedule::parse("0 0 * * * *").unwrap();
+        assert_eq!(
+            hourly.next_after(&time),
+            Some(Utc.with_ymd_and_hms(2023, 1, 1, 8, 0, 0).unwrap())
+        );
+        assert!(CronSchedule::parse("every tuesday").is_err());
+    }
+
+    #[test]
+    fn cron_schedule_uses_standard_days_of_the_week() {
+        // 2023-01-01 was a Sunday
+        let time = Utc.with_ymd_and_hms(2023, 1, 1, 7, 30, 0).unwrap();
+
+        let sundays = CronSchedule::parse("0 6 * * 0").unwrap();
+        assert_eq!(
+            sundays.next_after(&time),
+            Some(Utc.with_ymd_and_hms(2023, 1, 8, 6, 0, 0).unwrap())
+        );
+        let also_sundays = CronSchedule::parse("0 6 * * 7").unwrap();
+        assert_eq!(also_sundays.next_after(&time), sundays.next_after(&time));
+
+        let weekdays = CronSchedule::parse("0 9 * * 1-5").unwrap();
+        let mut next = time;
+        for day in 2..=6 {
+            next = weekdays.next_after(&next).unwrap();
+            assert_eq!(next, Utc.with_ymd_and_hms(2023, 1, day, 9, 0, 0).unwrap());
+        }
+        // Saturday and Sunday should be skipped
+        assert_eq!(
+            weekdays.next_after(&next),
+            Some(Utc.with_ymd_and_hms(2023, 1, 9, 9, 0, 0).unwrap())
+        );
+        // Names should be left alone
+        let named_weekdays = CronSchedule::parse("0 9 * * Mon-Fri").unwrap();
+        assert_eq!(named_weekdays.next_after(&time), weekdays.next_after(&time));
+
+        assert!(CronSchedule::parse("0 6 * * 8").is_err());
+        assert!(CronSchedule::parse("0 6 * * 5-1").is_err());
+    }
+}
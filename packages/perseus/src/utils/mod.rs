@@ -4,6 +4,8 @@ mod async_fn_trait;
 mod cache_res;
 #[cfg(any(client, doc))]
 mod checkpoint;
+#[cfg(engine)]
+mod cron_schedule;
 mod decode_time_str;
 #[cfg(any(client, doc))]
 mod fetch;
@@ -14,6 +16,8 @@ mod path_prefix;
 mod render;
 #[cfg(any(client, doc))]
 mod replace_head;
+#[cfg(engine)]
+mod server_timing;
 mod test;
 
 #[cfg(engine)]
@@ -22,6 +26,8 @@ pub(crate) use async_fn_trait::AsyncFnReturn;
 pub use cache_res::{cache_fallible_res, cache_res};
 #[cfg(any(client, doc))]
 pub use checkpoint::checkpoint;
+#[cfg(engine)]
+pub use cron_schedule::{CronSchedule, InvalidCronExpression};
 pub use decode_time_str::{ComputedDuration, InvalidDuration, PerseusDuration}; /* These have dummy equivalents for the browser */
 #[cfg(any(client, doc))]
 pub(crate) use fetch::fetch;
@@ -34,3 +40,5 @@ pub(crate) use render::render_or_hydrate;
 pub(crate) use render::ssr_fallible;
 #[cfg(any(client, doc))]
 pub(crate) use replace_head::replace_head;
+#[cfg(engine)]
+pub(crate) use server_timing::{server_timing_header, time_phase, time_phase_sync, time_request};
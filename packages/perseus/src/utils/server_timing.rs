@@ -0,0 +1,119 @@
+use http::HeaderValue;
+use std::{
+    cell::RefCell,
+    future::Future,
+    time::{Duration, Instant},
+};
+use tracing::Instrument;
+
+tokio::task_local! {
+    /// The total time spent in each phase of the request currently being
+    /// timed, in the order they were first entered.
+    static PHASES: RefCell<Vec<(&'static str, Duration)>>;
+}
+
+/// Runs the given future (which should handle a request) with timing of its
+/// phases enabled, returning its output and the total time spent in each
+/// phase, in the order they were first entered. This runs inside a `tracing`
+/// span for the request, under which the spans for each phase will be
+/// created.
+pub(crate) async fn time_request<F: Future>(
+    endpoint: &'static str,
+    fut: F,
+) -> (F::Output, Vec<(&'static str, Duration)>) {
+    let span = tracing::info_span!("perseus_request", endpoint);
+    PHASES
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = fut.await;
+            let phases = PHASES.with(|phases| phases.take());
+            (output, phases)
+        })
+        .instrument(span)
+        .await
+}
+
+/// Times the given future as the given phase of the current request. If the
+/// current request isn't being timed, this will just run the future.
+///
+/// Phases with the same name will have their durations added together, which
+/// means some phases (e.g. the request state of several widgets, which is
+/// generated concurrently) can take longer in total than the request itself.
+pub(crate) async fn time_phase<F: Future>(phase: &'static str, fut: F) -> F::Output {
+    if PHASES.try_with(|_| ()).is_err() {
+        return fut.await;
+    }
+
+    let span = tracing::info_span!("perseus_phase", phase);
+    let start = Instant::now();
+    let output = fut.instrument(span).await;
+    record_phase(phase, start.elapsed());
+    output
+}
+
+/// The same as `time_phase()`, but for synchronous work.
+pub(crate) fn time_phase_sync<R>(phase: &'static str, f: impl FnOnce() -> R) -> R {
+    if PHASES.try_with(|_| ()).is_err() {
+        return f();
+    }
+
+    let span = tracing::info_span!("perseus_phase", phase);
+    let start = Instant::now();
+    let output = span.in_scope(f);
+    record_phase(phase, start.elapsed());
+    output
+}
+
+/// Formats the given phase timings, and the total time taken by a request, as
+/// the value of a `Server-Timing` HTTP header.
+pub(crate) fn server_timing_header(
+    phases: &[(&'static str, Duration)],
+    total: Duration,
+) -> HeaderValue {
+    let value = phases
+        .iter()
+        .chain(std::iter::once(&("total", total)))
+        .map(|(phase, duration)| format!("{};dur={:.3}", phase, duration.as_secs_f64() * 1000.0))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Phase names are all valid tokens
+    HeaderValue::from_str(&value).unwrap()
+}
+
+fn record_phase(phase: &'static str, duration: Duration) {
+    // If this has somehow ended up in another task, we'll just lose the timing
+    let _ = PHASES.try_with(|phases| {
+        let mut phases = phases.borrow_mut();
+        match phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += duration,
+            None => phases.push((phase, duration)),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_should_be_recorded_in_order() {
+        let (output, phases) = futures::executor::block_on(time_request("test", async {
+            time_phase("first", async {}).await;
+            time_phase_sync("second", || ());
+            time_phase("first", async { 42 }).await
+        }));
+        assert_eq!(output, 42);
+        assert_eq!(
+            phases.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+
+        let header = server_timing_header(&phases, Duration::from_millis(5));
+        assert!(header.to_str().unwrap().ends_with("total;dur=5.000"));
+    }
+
+    #[test]
+    fn phases_should_not_be_recorded_outside_requests() {
+        assert_eq!(time_phase_sync("phase", || 42), 42);
+        assert!(PHASES.try_with(|_| ()).is_err());
+    }
+}